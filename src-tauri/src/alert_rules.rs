@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::analytics::AlertLevel;
+use crate::error::{AppError, AppResult};
+
+/// What a rule watches. Each variant compares one metric against one threshold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Remaining credits drop below `credits`
+    BalanceBelow { credits: u32 },
+    /// Remaining credits drop below `percent` of the cycle allotment
+    CyclePercentRemainingBelow { percent: f64 },
+    /// Forecast time to depletion drops below `hours`
+    HoursToDepletionBelow { hours: f64 },
+    /// Consumption rate rises above `credits_per_hour`
+    BurnRateAbove { credits_per_hour: f64 },
+    /// Consumption rate rises above `multiple` times the account's own average usage
+    BurnRateAboveAverage { multiple: f64 },
+    /// Cycle consumption runs more than `percent` ahead of an even pace
+    BudgetPacingAbove { percent: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Below,
+    Above,
+}
+
+/// Where a fired rule is delivered
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Desktop,
+    /// One of the configured webhooks, by id
+    Webhook { id: String },
+    /// One of the configured Slack / Discord / Teams channels, by id. Rules that name no chat
+    /// channel go to every chat channel subscribed to their severity.
+    Chat { id: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    pub severity: AlertLevel,
+    #[serde(default = "default_channels")]
+    pub channels: Vec<NotificationChannel>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_channels() -> Vec<NotificationChannel> {
+    vec![NotificationChannel::Desktop]
+}
+
+/// Everything known about the account at the moment rules are evaluated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertContext {
+    pub balance: u32,
+    /// Credits available for the whole billing cycle
+    pub cycle_allotment: Option<i64>,
+    /// Credits consumed so far this cycle
+    pub cycle_consumed: Option<i64>,
+    /// Share of the billing cycle that has elapsed (0.0 - 1.0)
+    pub cycle_elapsed_fraction: Option<f64>,
+    pub hours_to_depletion: Option<f64>,
+    pub burn_rate_per_hour: f64,
+    /// Average credits used per recorded session, `None` until there is usage history
+    pub average_usage: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleEvaluation {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub severity: AlertLevel,
+    pub channels: Vec<NotificationChannel>,
+    pub triggered: bool,
+    /// Current value of the watched metric, `None` when it can't be computed yet
+    pub value: Option<f64>,
+    pub threshold: f64,
+    pub message: String,
+}
+
+impl AlertCondition {
+    pub fn threshold(&self) -> f64 {
+        match self {
+            AlertCondition::BalanceBelow { credits } => *credits as f64,
+            AlertCondition::CyclePercentRemainingBelow { percent } => *percent,
+            AlertCondition::HoursToDepletionBelow { hours } => *hours,
+            AlertCondition::BurnRateAbove { credits_per_hour } => *credits_per_hour,
+            AlertCondition::BurnRateAboveAverage { multiple } => *multiple,
+            AlertCondition::BudgetPacingAbove { percent } => *percent,
+        }
+    }
+
+    pub fn comparison(&self) -> Comparison {
+        match self {
+            AlertCondition::BalanceBelow { .. }
+            | AlertCondition::CyclePercentRemainingBelow { .. }
+            | AlertCondition::HoursToDepletionBelow { .. } => Comparison::Below,
+            AlertCondition::BurnRateAbove { .. }
+            | AlertCondition::BurnRateAboveAverage { .. }
+            | AlertCondition::BudgetPacingAbove { .. } => Comparison::Above,
+        }
+    }
+
+    /// Current value of the metric this condition watches
+    pub fn measure(&self, context: &AlertContext) -> Option<f64> {
+        match self {
+            AlertCondition::BalanceBelow { .. } => Some(context.balance as f64),
+            AlertCondition::CyclePercentRemainingBelow { .. } => context.cycle_allotment
+                .filter(|allotment| *allotment > 0)
+                .map(|allotment| context.balance as f64 / allotment as f64 * 100.0),
+            AlertCondition::HoursToDepletionBelow { .. } => context.hours_to_depletion,
+            AlertCondition::BurnRateAbove { .. } => Some(context.burn_rate_per_hour),
+            AlertCondition::BurnRateAboveAverage { .. } => context.average_usage
+                .filter(|average| *average > 0.0)
+                .map(|average| context.burn_rate_per_hour / average),
+            AlertCondition::BudgetPacingAbove { .. } => {
+                let allotment = context.cycle_allotment.filter(|a| *a > 0)? as f64;
+                let consumed = context.cycle_consumed? as f64;
+                let elapsed = context.cycle_elapsed_fraction.filter(|f| *f > 0.0)?;
+                let expected = allotment * elapsed.min(1.0);
+                Some((consumed / expected - 1.0) * 100.0)
+            }
+        }
+    }
+
+    pub fn is_met_by(&self, value: f64) -> bool {
+        match self.comparison() {
+            Comparison::Below => value <= self.threshold(),
+            Comparison::Above => value > self.threshold(),
+        }
+    }
+
+    pub fn describe(&self, value: f64) -> String {
+        match self {
+            AlertCondition::BalanceBelow { .. } => {
+                format!("{} credits remaining", value as i64)
+            }
+            AlertCondition::CyclePercentRemainingBelow { .. } => {
+                format!("{:.0}% of this cycle's credits remaining", value)
+            }
+            AlertCondition::HoursToDepletionBelow { .. } => {
+                format!("Credits will run out in {:.1} hours at current usage rate", value)
+            }
+            AlertCondition::BurnRateAbove { .. } => {
+                format!("Usage rate is {:.1} credits/hour", value)
+            }
+            AlertCondition::BurnRateAboveAverage { .. } => {
+                format!("Usage rate is {:.1}x your average", value)
+            }
+            AlertCondition::BudgetPacingAbove { .. } => {
+                format!("Spending is {:.0}% ahead of an even pace for this cycle", value)
+            }
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            AlertCondition::BalanceBelow { .. } => "balance_below",
+            AlertCondition::CyclePercentRemainingBelow { .. } => "cycle_percent_remaining_below",
+            AlertCondition::HoursToDepletionBelow { .. } => "hours_to_depletion_below",
+            AlertCondition::BurnRateAbove { .. } => "burn_rate_above",
+            AlertCondition::BurnRateAboveAverage { .. } => "burn_rate_above_average",
+            AlertCondition::BudgetPacingAbove { .. } => "budget_pacing_above",
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let threshold = self.threshold();
+        if !threshold.is_finite() {
+            return Err("threshold must be a finite number".to_string());
+        }
+
+        match self {
+            AlertCondition::CyclePercentRemainingBelow { percent } if !(0.0..=100.0).contains(percent) => {
+                Err("percent must be between 0 and 100".to_string())
+            }
+            AlertCondition::HoursToDepletionBelow { hours } if *hours <= 0.0 => {
+                Err("hours must be greater than 0".to_string())
+            }
+            AlertCondition::BurnRateAbove { credits_per_hour } if *credits_per_hour <= 0.0 => {
+                Err("credits per hour must be greater than 0".to_string())
+            }
+            AlertCondition::BurnRateAboveAverage { multiple } if *multiple <= 0.0 => {
+                Err("multiple must be greater than 0".to_string())
+            }
+            AlertCondition::BudgetPacingAbove { percent } if *percent < 0.0 => {
+                Err("pacing percent cannot be negative".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl AlertRule {
+    pub fn evaluate(&self, context: &AlertContext) -> RuleEvaluation {
+        let value = self.condition.measure(context);
+        let triggered = value.map(|v| self.condition.is_met_by(v)).unwrap_or(false);
+
+        RuleEvaluation {
+            rule_id: self.id.clone(),
+            rule_name: self.name.clone(),
+            severity: self.severity,
+            channels: self.channels.clone(),
+            triggered,
+            value,
+            threshold: self.condition.threshold(),
+            message: value.map(|v| self.condition.describe(v)).unwrap_or_default(),
+        }
+    }
+}
+
+/// How far above the account's own average usage the default "High Usage Detected" rule warns
+pub const DEFAULT_HIGH_USAGE_MULTIPLE: f64 = 2.0;
+
+/// Rules equivalent to the original hardcoded alerts, driven by the configured balance thresholds
+pub fn default_rules(low_balance_threshold: u32, critical_balance_threshold: u32) -> Vec<AlertRule> {
+    vec![
+        AlertRule {
+            id: "critical_balance".to_string(),
+            name: "Critical Balance Alert".to_string(),
+            enabled: true,
+            condition: AlertCondition::BalanceBelow { credits: critical_balance_threshold },
+            severity: AlertLevel::Critical,
+            channels: default_channels(),
+        },
+        AlertRule {
+            id: "low_balance".to_string(),
+            name: "Low Balance Warning".to_string(),
+            enabled: true,
+            condition: AlertCondition::BalanceBelow { credits: low_balance_threshold },
+            severity: AlertLevel::Warning,
+            channels: default_channels(),
+        },
+        AlertRule {
+            id: "time_critical".to_string(),
+            name: "Credits Depleting Soon".to_string(),
+            enabled: true,
+            condition: AlertCondition::HoursToDepletionBelow { hours: 2.0 },
+            severity: AlertLevel::Critical,
+            channels: default_channels(),
+        },
+        AlertRule {
+            id: "time_warning".to_string(),
+            name: "Credits Running Low".to_string(),
+            enabled: true,
+            condition: AlertCondition::HoursToDepletionBelow { hours: 24.0 },
+            severity: AlertLevel::Warning,
+            channels: default_channels(),
+        },
+        AlertRule {
+            id: "high_usage".to_string(),
+            name: "High Usage Detected".to_string(),
+            enabled: true,
+            condition: AlertCondition::BurnRateAboveAverage { multiple: DEFAULT_HIGH_USAGE_MULTIPLE },
+            severity: AlertLevel::Warning,
+            channels: default_channels(),
+        },
+    ]
+}
+
+pub fn validate_rules(rules: &[AlertRule]) -> AppResult<()> {
+    let mut ids = HashSet::new();
+
+    for rule in rules {
+        let invalid = |reason: String| AppError::Config(
+            config::ConfigError::Message(format!("Alert rule '{}': {}", rule.id, reason))
+        );
+
+        if rule.id.trim().is_empty() {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Alert rule id cannot be empty".to_string())
+            ));
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(invalid("duplicate rule id".to_string()));
+        }
+        if rule.name.trim().is_empty() {
+            return Err(invalid("name cannot be empty".to_string()));
+        }
+        if rule.channels.is_empty() {
+            return Err(invalid("at least one channel is required".to_string()));
+        }
+        rule.condition.validate().map_err(invalid)?;
+    }
+
+    Ok(())
+}
+
+/// Evaluate every enabled rule
+pub fn evaluate_rules(rules: &[AlertRule], context: &AlertContext) -> Vec<RuleEvaluation> {
+    rules.iter()
+        .filter(|rule| rule.enabled)
+        .map(|rule| rule.evaluate(context))
+        .collect()
+}
+
+/// Triggered evaluations worth notifying about: when several rules of the same kind fire
+/// (e.g. low and critical balance), only the most severe one is kept.
+pub fn select_notifications<'a>(rules: &[AlertRule], evaluations: &'a [RuleEvaluation]) -> Vec<&'a RuleEvaluation> {
+    let kind_of = |rule_id: &str| rules.iter()
+        .find(|rule| rule.id == rule_id)
+        .map(|rule| rule.condition.kind());
+
+    evaluations.iter()
+        .filter(|evaluation| evaluation.triggered)
+        .filter(|evaluation| {
+            let kind = kind_of(&evaluation.rule_id);
+            !evaluations.iter().any(|other| {
+                other.triggered
+                    && other.severity > evaluation.severity
+                    && kind_of(&other.rule_id) == kind
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(balance: u32) -> AlertContext {
+        AlertContext {
+            balance,
+            cycle_allotment: Some(1000),
+            cycle_consumed: Some(600),
+            cycle_elapsed_fraction: Some(0.4),
+            hours_to_depletion: Some(10.0),
+            burn_rate_per_hour: 5.0,
+            average_usage: Some(4.0),
+        }
+    }
+
+    #[test]
+    fn test_default_rules_follow_thresholds() {
+        let rules = default_rules(500, 100);
+        let evaluations = evaluate_rules(&rules, &context(80));
+        let selected: Vec<&str> = select_notifications(&rules, &evaluations)
+            .iter()
+            .map(|e| e.rule_id.as_str())
+            .collect();
+
+        // Critical supersedes low balance; the 24h depletion warning is independent
+        assert_eq!(selected, vec!["critical_balance", "time_warning"]);
+
+        let busy = AlertContext { burn_rate_per_hour: 9.0, ..context(800) };
+        let evaluations = evaluate_rules(&rules, &busy);
+        let selected: Vec<&str> = select_notifications(&rules, &evaluations)
+            .iter()
+            .map(|e| e.rule_id.as_str())
+            .collect();
+        assert_eq!(selected, vec!["time_warning", "high_usage"]);
+    }
+
+    #[test]
+    fn test_high_usage_is_relative_to_average() {
+        let condition = AlertCondition::BurnRateAboveAverage { multiple: 2.0 };
+
+        let light = AlertContext { burn_rate_per_hour: 8.0, average_usage: Some(10.0), ..context(800) };
+        assert!(!condition.is_met_by(condition.measure(&light).unwrap()));
+
+        // The same rate is high usage for an account that normally uses far less
+        let heavy = AlertContext { average_usage: Some(3.0), ..light.clone() };
+        let value = condition.measure(&heavy).unwrap();
+        assert!(condition.is_met_by(value));
+        assert_eq!(condition.describe(value), "Usage rate is 2.7x your average");
+
+        let no_history = AlertContext { average_usage: None, ..light };
+        assert_eq!(condition.measure(&no_history), None);
+    }
+
+    #[test]
+    fn test_budget_pacing_and_cycle_percent() {
+        let pacing = AlertCondition::BudgetPacingAbove { percent: 25.0 };
+        // 600 consumed vs 400 expected = 50% ahead
+        let value = pacing.measure(&context(400)).unwrap();
+        assert!((value - 50.0).abs() < 1e-9);
+        assert!(pacing.is_met_by(value));
+
+        let remaining = AlertCondition::CyclePercentRemainingBelow { percent: 30.0 };
+        assert_eq!(remaining.measure(&context(250)), Some(25.0));
+
+        let unknown = AlertContext { balance: 10, ..Default::default() };
+        assert_eq!(pacing.measure(&unknown), None);
+    }
+
+    #[test]
+    fn test_validate_rules_rejects_bad_input() {
+        let mut rules = default_rules(500, 100);
+        assert!(validate_rules(&rules).is_ok());
+
+        rules[1].id = "critical_balance".to_string();
+        assert!(validate_rules(&rules).is_err());
+
+        let mut rules = default_rules(500, 100);
+        rules[0].condition = AlertCondition::CyclePercentRemainingBelow { percent: 150.0 };
+        assert!(validate_rules(&rules).is_err());
+
+        let mut rules = default_rules(500, 100);
+        rules[0].channels.clear();
+        assert!(validate_rules(&rules).is_err());
+    }
+}
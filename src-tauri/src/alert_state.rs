@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::alert_rules::{AlertRule, Comparison, NotificationChannel, RuleEvaluation};
+use crate::analytics::AlertLevel;
+
+/// Lifecycle of a single alert rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// Waiting for the condition to be met
+    #[default]
+    Armed,
+    /// Condition met and notified
+    Firing,
+    /// Firing, but the user has seen it; only an escalation notifies again
+    Acknowledged,
+    /// Condition cleared past the hysteresis margin; armed for the next crossing
+    Resolved,
+}
+
+impl AlertStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertStatus::Armed => "armed",
+            AlertStatus::Firing => "firing",
+            AlertStatus::Acknowledged => "acknowledged",
+            AlertStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "armed" => Some(AlertStatus::Armed),
+            "firing" => Some(AlertStatus::Firing),
+            "acknowledged" => Some(AlertStatus::Acknowledged),
+            "resolved" => Some(AlertStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+pub fn level_as_str(level: AlertLevel) -> &'static str {
+    match level {
+        AlertLevel::Info => "info",
+        AlertLevel::Warning => "warning",
+        AlertLevel::Critical => "critical",
+    }
+}
+
+pub fn parse_level(value: &str) -> Option<AlertLevel> {
+    match value {
+        "info" => Some(AlertLevel::Info),
+        "warning" => Some(AlertLevel::Warning),
+        "critical" => Some(AlertLevel::Critical),
+        _ => None,
+    }
+}
+
+/// Persisted state of one rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertState {
+    pub rule_id: String,
+    pub status: AlertStatus,
+    pub severity: AlertLevel,
+    /// Metric value at the last notification, escalation is measured from here
+    pub notified_value: Option<f64>,
+    pub last_value: Option<f64>,
+    pub fired_at: Option<DateTime<Utc>>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlertState {
+    pub fn armed(rule_id: &str, severity: AlertLevel, now: DateTime<Utc>) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            status: AlertStatus::Armed,
+            severity,
+            notified_value: None,
+            last_value: None,
+            fired_at: None,
+            acknowledged_at: None,
+            resolved_at: None,
+            updated_at: now,
+        }
+    }
+
+    /// Mark a firing alert as seen. Returns false when there is nothing to acknowledge.
+    pub fn acknowledge(&mut self, now: DateTime<Utc>) -> bool {
+        if self.status != AlertStatus::Firing {
+            return false;
+        }
+        self.status = AlertStatus::Acknowledged;
+        self.acknowledged_at = Some(now);
+        self.updated_at = now;
+        true
+    }
+}
+
+/// Margins that keep an alert from flapping around its threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertBehaviorSettings {
+    /// How far past the threshold (percent of the threshold) the metric must recover to resolve
+    pub hysteresis_percent: f64,
+    /// How much further (percent of the threshold) the metric must worsen to notify again
+    pub escalation_step_percent: f64,
+}
+
+impl Default for AlertBehaviorSettings {
+    fn default() -> Self {
+        Self {
+            hysteresis_percent: 10.0,
+            escalation_step_percent: 25.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionKind {
+    Fired,
+    Escalated,
+    Resolved,
+}
+
+/// A state change worth telling someone about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertTransition {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub kind: TransitionKind,
+    pub severity: AlertLevel,
+    pub channels: Vec<NotificationChannel>,
+    pub value: Option<f64>,
+    pub threshold: f64,
+    pub message: String,
+}
+
+fn margin(threshold: f64, percent: f64) -> f64 {
+    threshold.abs() * percent.max(0.0) / 100.0
+}
+
+/// Whether `value` has recovered far enough past the threshold to resolve
+fn has_recovered(comparison: Comparison, value: f64, threshold: f64, settings: &AlertBehaviorSettings) -> bool {
+    let margin = margin(threshold, settings.hysteresis_percent);
+    match comparison {
+        Comparison::Below => value >= threshold + margin,
+        Comparison::Above => value <= threshold - margin,
+    }
+}
+
+/// Whether `value` is at least one escalation step worse than `notified`
+fn has_worsened(comparison: Comparison, value: f64, notified: f64, threshold: f64, settings: &AlertBehaviorSettings) -> bool {
+    let step = margin(threshold, settings.escalation_step_percent);
+    if step <= 0.0 {
+        return false;
+    }
+    match comparison {
+        Comparison::Below => value <= notified - step,
+        Comparison::Above => value >= notified + step,
+    }
+}
+
+/// Advance one rule's state with a fresh evaluation
+pub fn advance(
+    state: &mut AlertState,
+    rule: &AlertRule,
+    evaluation: &RuleEvaluation,
+    settings: &AlertBehaviorSettings,
+    now: DateTime<Utc>,
+) -> Option<TransitionKind> {
+    // Without a value there's nothing to compare, keep whatever state we're in
+    let value = evaluation.value?;
+    let comparison = rule.condition.comparison();
+
+    state.last_value = Some(value);
+    state.severity = evaluation.severity;
+    state.updated_at = now;
+
+    match state.status {
+        AlertStatus::Armed | AlertStatus::Resolved if evaluation.triggered => {
+            state.status = AlertStatus::Firing;
+            state.notified_value = Some(value);
+            state.fired_at = Some(now);
+            state.acknowledged_at = None;
+            state.resolved_at = None;
+            Some(TransitionKind::Fired)
+        }
+        AlertStatus::Firing | AlertStatus::Acknowledged => {
+            let notified = state.notified_value.unwrap_or(value);
+            if evaluation.triggered && has_worsened(comparison, value, notified, evaluation.threshold, settings) {
+                state.status = AlertStatus::Firing;
+                state.notified_value = Some(value);
+                state.acknowledged_at = None;
+                Some(TransitionKind::Escalated)
+            } else if !evaluation.triggered && has_recovered(comparison, value, evaluation.threshold, settings) {
+                state.status = AlertStatus::Resolved;
+                state.notified_value = None;
+                state.resolved_at = Some(now);
+                Some(TransitionKind::Resolved)
+            } else {
+                None
+            }
+        }
+        AlertStatus::Armed | AlertStatus::Resolved => None,
+    }
+}
+
+/// Advance every evaluated rule, creating armed states for rules seen for the first time.
+/// Returns the transitions that happened; `states` holds the updated states afterwards.
+pub fn advance_all(
+    states: &mut HashMap<String, AlertState>,
+    rules: &[AlertRule],
+    evaluations: &[RuleEvaluation],
+    settings: &AlertBehaviorSettings,
+    now: DateTime<Utc>,
+) -> Vec<AlertTransition> {
+    let mut transitions = Vec::new();
+
+    for evaluation in evaluations {
+        let rule = match rules.iter().find(|rule| rule.id == evaluation.rule_id) {
+            Some(rule) => rule,
+            None => continue,
+        };
+
+        let state = states.entry(evaluation.rule_id.clone())
+            .or_insert_with(|| AlertState::armed(&evaluation.rule_id, evaluation.severity, now));
+
+        if let Some(kind) = advance(state, rule, evaluation, settings, now) {
+            let message = match kind {
+                TransitionKind::Resolved => format!("{} has recovered", rule.name),
+                TransitionKind::Fired | TransitionKind::Escalated => evaluation.message.clone(),
+            };
+            transitions.push(AlertTransition {
+                rule_id: evaluation.rule_id.clone(),
+                rule_name: evaluation.rule_name.clone(),
+                kind,
+                severity: evaluation.severity,
+                channels: evaluation.channels.clone(),
+                value: evaluation.value,
+                threshold: evaluation.threshold,
+                message,
+            });
+        }
+    }
+
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert_rules::{AlertCondition, AlertContext};
+
+    fn balance_rule() -> AlertRule {
+        AlertRule {
+            id: "low".to_string(),
+            name: "Low".to_string(),
+            enabled: true,
+            condition: AlertCondition::BalanceBelow { credits: 500 },
+            severity: AlertLevel::Warning,
+            channels: vec![NotificationChannel::Desktop],
+        }
+    }
+
+    fn step(states: &mut HashMap<String, AlertState>, balance: u32) -> Vec<TransitionKind> {
+        let rules = vec![balance_rule()];
+        let context = AlertContext { balance, ..Default::default() };
+        let evaluations: Vec<RuleEvaluation> = rules.iter().map(|r| r.evaluate(&context)).collect();
+        advance_all(states, &rules, &evaluations, &AlertBehaviorSettings::default(), Utc::now())
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_fires_once_and_escalates_when_worse() {
+        let mut states = HashMap::new();
+
+        assert!(step(&mut states, 800).is_empty());
+        assert_eq!(step(&mut states, 450), vec![TransitionKind::Fired]);
+        assert!(step(&mut states, 440).is_empty());
+        assert!(step(&mut states, 400).is_empty());
+
+        // 25% of 500 below the last notified value (450)
+        assert_eq!(step(&mut states, 325), vec![TransitionKind::Escalated]);
+        assert_eq!(states["low"].notified_value, Some(325.0));
+    }
+
+    #[test]
+    fn test_hysteresis_and_rearm() {
+        let mut states = HashMap::new();
+        step(&mut states, 450);
+
+        // Back above the threshold but inside the 10% margin: still firing
+        assert!(step(&mut states, 520).is_empty());
+        assert_eq!(states["low"].status, AlertStatus::Firing);
+
+        // Top-up clears the margin
+        assert_eq!(step(&mut states, 900), vec![TransitionKind::Resolved]);
+        assert_eq!(states["low"].status, AlertStatus::Resolved);
+
+        assert_eq!(step(&mut states, 480), vec![TransitionKind::Fired]);
+    }
+
+    #[test]
+    fn test_acknowledged_alert_stays_quiet_until_escalation() {
+        let mut states = HashMap::new();
+        step(&mut states, 450);
+        assert!(states.get_mut("low").unwrap().acknowledge(Utc::now()));
+
+        assert!(step(&mut states, 420).is_empty());
+        assert_eq!(states["low"].status, AlertStatus::Acknowledged);
+
+        assert_eq!(step(&mut states, 300), vec![TransitionKind::Escalated]);
+        assert_eq!(states["low"].status, AlertStatus::Firing);
+    }
+}
@@ -1,8 +1,11 @@
 use std::sync::Arc;
-use chrono::{DateTime, Utc, Timelike};
+use chrono::{DateTime, Duration, NaiveDate, Utc, Timelike};
 use serde::{Deserialize, Serialize};
 use crate::database::{Database, BalanceRecord, UsageRecord};
 use crate::error::AppResult;
+use crate::forecast::{self, BacktestReport, ForecastMethod};
+use crate::statistics::{self, StatisticsSource, UsageStatistics};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageAnalytics {
@@ -18,6 +21,8 @@ pub struct UsageAnalytics {
     pub efficiency_score: f64,
     pub balance_history: Vec<BalanceDataPoint>,
     pub usage_history: Vec<UsageDataPoint>,
+    #[serde(default)]
+    pub forecast_method: ForecastMethod,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +109,7 @@ impl AnalyticsEngine {
             efficiency_score,
             balance_history: balance_data_points,
             usage_history: usage_data_points,
+            forecast_method: ForecastMethod::LastDay,
         })
     }
     
@@ -239,6 +245,73 @@ impl AnalyticsEngine {
         Ok(peak_hour)
     }
     
+    /// Moving averages, percentiles and busiest periods over the last `days` days.
+    /// Without a known `cycle_start` the last 30 days are treated as the current cycle.
+    pub async fn calculate_usage_statistics(
+        &self,
+        days: u32,
+        source: StatisticsSource,
+        cycle_start: Option<NaiveDate>,
+    ) -> AppResult<UsageStatistics> {
+        let today = Utc::now().date_naive();
+        let totals = self.daily_totals(days, source).await?;
+        let cycle_start = cycle_start.unwrap_or(today - Duration::days(29));
+
+        Ok(statistics::calculate_statistics(source, &totals, today, cycle_start))
+    }
+
+    async fn daily_totals(&self, days: u32, source: StatisticsSource) -> AppResult<BTreeMap<NaiveDate, i64>> {
+        let days = days.max(1);
+        let usage_history = self.database.get_usage_history(days.saturating_mul(24)).await?;
+        let consumption = self.database.get_daily_consumption(days).await?;
+
+        let window_start = Utc::now().date_naive() - Duration::days(days as i64 - 1);
+
+        let mut totals = statistics::select_daily_totals(
+            source,
+            statistics::local_daily_totals(&usage_history),
+            statistics::server_daily_totals(&consumption),
+        );
+        totals.retain(|date, _| *date >= window_start);
+
+        Ok(totals)
+    }
+
+    /// Daily usage for fully elapsed days (today is still in progress and left out)
+    async fn completed_daily_usage(&self, days: u32) -> AppResult<Vec<f64>> {
+        let totals = self.daily_totals(days, StatisticsSource::Combined).await?;
+        let yesterday = Utc::now().date_naive() - Duration::days(1);
+
+        Ok(statistics::build_daily_series(&totals, yesterday)
+            .into_iter()
+            .map(|point| point.credits as f64)
+            .collect())
+    }
+
+    /// Score every forecasting method against the stored history
+    pub async fn run_forecast_backtest(&self, days: u32, horizon_days: usize) -> AppResult<BacktestReport> {
+        let series = self.completed_daily_usage(days).await?;
+        Ok(forecast::backtest(&series, horizon_days, 7))
+    }
+
+    /// Replace the sample-rate depletion estimate with one from `method`, the same estimator the
+    /// backtest scores. Without enough history or usage the sample-rate estimate and its label stay.
+    pub async fn apply_forecast_method(&self, analytics: &mut UsageAnalytics, method: ForecastMethod) -> AppResult<()> {
+        let series = self.completed_daily_usage(90).await?;
+        let daily_rate = method.predict_daily_rate(&series, 7);
+
+        if let (Some(balance), Some(daily_rate)) = (analytics.current_balance, daily_rate) {
+            if daily_rate > 0.0 {
+                let days_remaining = balance as f64 / daily_rate;
+                analytics.estimated_days_remaining = Some(days_remaining);
+                analytics.estimated_hours_remaining = Some(days_remaining * 24.0);
+                analytics.forecast_method = method;
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn get_usage_prediction(&self, hours_ahead: u32) -> AppResult<f64> {
         let analytics = self.calculate_usage_analytics(24).await?;
         
@@ -294,7 +367,7 @@ pub struct AlertInfo {
     pub estimated_time_remaining: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum AlertLevel {
     Info,
     Warning,
@@ -1,9 +1,60 @@
-use reqwest::{Client, header::{HeaderMap, HeaderValue, COOKIE, USER_AGENT}};
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use reqwest::{Client, StatusCode, header::{HeaderMap, HeaderValue, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH, USER_AGENT}};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use crate::consumption_query::{ConsumptionQuery, Granularity, GroupBy};
 use crate::error::{AppError, AppResult};
+use crate::fixtures::FixtureStore;
+use crate::http_cache::{CachedEndpoint, CachedResponse, Freshness, HttpCache, HttpCacheSettings};
+use crate::network::NetworkSettings;
+use crate::retry::RetryPolicy;
+use crate::schema_drift::{self, DecodeDiagnostic, ExpectedField, ExpectedSchema, JsonKind};
+
+const DEFAULT_AUGMENT_URL: &str = "https://app.augmentcode.com";
+
+/// Where the Augment account lives: the public app, an enterprise tenant or a local mock server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AugmentEndpoints {
+    /// Origin the `/api/...` endpoints are served from
+    pub api_base_url: String,
+    /// Page opened for login; the session cookie is read for this origin
+    pub login_url: String,
+}
 
-const AUGMENT_BASE_URL: &str = "https://app.augmentcode.com";
+impl Default for AugmentEndpoints {
+    fn default() -> Self {
+        Self {
+            api_base_url: DEFAULT_AUGMENT_URL.to_string(),
+            login_url: DEFAULT_AUGMENT_URL.to_string(),
+        }
+    }
+}
+
+impl AugmentEndpoints {
+    pub fn validate(&self) -> AppResult<()> {
+        for (name, value) in [("API base URL", &self.api_base_url), ("login URL", &self.login_url)] {
+            match url::Url::parse(value) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => {}
+                _ => return Err(AppError::Config(
+                    config::ConfigError::Message(format!("Augment {} must be an http(s) URL: '{}'", name, value))
+                )),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn login_url(&self) -> AppResult<url::Url> {
+        url::Url::parse(&self.login_url)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid Augment login URL '{}': {}", self.login_url, e)))
+    }
+
+    /// Whether `url` is on the login origin (scheme, host and port)
+    pub fn is_login_origin(&self, url: &url::Url) -> bool {
+        self.login_url().is_ok_and(|login| login.origin() == url.origin())
+    }
+}
 
 /// Response from /api/credits endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,55 +66,136 @@ pub struct CreditsResponse {
     pub usage_units_consumed_this_billing_cycle: i64,
 }
 
-/// Response from /api/subscription endpoint
+/// Response from /api/subscription endpoint. Only the plan and billing period are required;
+/// other fields fall back to defaults if Augment drops or retypes them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SubscriptionResponse {
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub portal_url: Option<String>,
     pub plan_id: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub plan_type: i32,
     pub plan_name: String,
     pub billing_period_end: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub trial_period_end: Option<String>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub credit_consumption_min_date: Option<String>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub credits_renewing_each_billing_cycle: i64,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub credits_included_this_billing_cycle: i64,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub billing_cycle_billing_amount: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub monthly_total_cost: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub price_per_seat: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub max_num_seats: i32,
-    pub number_of_seats_this_billing_cycle: i32,
-    pub number_of_seats_next_billing_cycle: i32,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
+    pub number_of_seats_this_billing_cycle: Option<i32>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
+    pub number_of_seats_next_billing_cycle: Option<i32>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub subscription_end_date: Option<String>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub plan_is_expired: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub auto_top_up_available: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub teams_allowed: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub additional_usage_unit_cost: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub scheduled_target_plan_id: Option<String>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub usage_unit_display_name: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub usage_units_per_seat: i64,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub plan_facts: Vec<String>,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub trial_grant: i64,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub cancelled_due_to_payment_failure: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub is_cancellation_immediate: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub next_billing_cycle_plan_name: String,
 }
 
-/// Response from /api/user endpoint
+impl ExpectedSchema for SubscriptionResponse {
+    const FIELDS: &'static [ExpectedField] = &[
+        ExpectedField::nullable("portalUrl", JsonKind::String),
+        ExpectedField::new("planId", JsonKind::String),
+        ExpectedField::new("planType", JsonKind::Number),
+        ExpectedField::new("planName", JsonKind::String),
+        ExpectedField::new("billingPeriodEnd", JsonKind::String),
+        ExpectedField::nullable("trialPeriodEnd", JsonKind::String),
+        ExpectedField::nullable("creditConsumptionMinDate", JsonKind::String),
+        ExpectedField::new("creditsRenewingEachBillingCycle", JsonKind::Number),
+        ExpectedField::new("creditsIncludedThisBillingCycle", JsonKind::Number),
+        ExpectedField::new("billingCycleBillingAmount", JsonKind::String),
+        ExpectedField::new("monthlyTotalCost", JsonKind::String),
+        ExpectedField::new("pricePerSeat", JsonKind::String),
+        ExpectedField::new("maxNumSeats", JsonKind::Number),
+        ExpectedField::new("numberOfSeatsThisBillingCycle", JsonKind::Number),
+        ExpectedField::new("numberOfSeatsNextBillingCycle", JsonKind::Number),
+        ExpectedField::nullable("subscriptionEndDate", JsonKind::String),
+        ExpectedField::new("planIsExpired", JsonKind::Bool),
+        ExpectedField::new("autoTopUpAvailable", JsonKind::Bool),
+        ExpectedField::new("teamsAllowed", JsonKind::Bool),
+        ExpectedField::new("additionalUsageUnitCost", JsonKind::String),
+        ExpectedField::nullable("scheduledTargetPlanId", JsonKind::String),
+        ExpectedField::new("usageUnitDisplayName", JsonKind::String),
+        ExpectedField::new("usageUnitsPerSeat", JsonKind::Number),
+        ExpectedField::new("planFacts", JsonKind::Array),
+        ExpectedField::new("trialGrant", JsonKind::Number),
+        ExpectedField::new("cancelledDueToPaymentFailure", JsonKind::Bool),
+        ExpectedField::new("isCancellationImmediate", JsonKind::Bool),
+        ExpectedField::new("nextBillingCyclePlanName", JsonKind::String),
+    ];
+}
+
+/// Response from /api/user endpoint; only the email is required
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
     pub email: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub is_admin: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub is_self_serve_team_member: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub tenant_tier: String,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub is_subscription_pending: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub show_team_management_link: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub user_source_submitted: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub business_email_verified: bool,
+    #[serde(default, deserialize_with = "schema_drift::or_default")]
     pub preferred_team_name: String,
 }
 
+impl ExpectedSchema for UserResponse {
+    const FIELDS: &'static [ExpectedField] = &[
+        ExpectedField::new("email", JsonKind::String),
+        ExpectedField::new("isAdmin", JsonKind::Bool),
+        ExpectedField::new("isSelfServeTeamMember", JsonKind::Bool),
+        ExpectedField::new("tenantTier", JsonKind::String),
+        ExpectedField::new("isSubscriptionPending", JsonKind::Bool),
+        ExpectedField::new("showTeamManagementLink", JsonKind::Bool),
+        ExpectedField::new("userSourceSubmitted", JsonKind::Bool),
+        ExpectedField::new("businessEmailVerified", JsonKind::Bool),
+        ExpectedField::new("preferredTeamName", JsonKind::String),
+    ];
+}
+
 /// Response from /api/credit-analytics-info endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -124,6 +256,14 @@ pub struct ActivityUsage {
     pub credits: i64,
 }
 
+/// Usage of one model or activity type on one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyGroupUsage {
+    pub date: String,
+    pub group_key: String,
+    pub credits: i64,
+}
+
 /// Combined balance info for the app (legacy, not used)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AugmentBalanceInfo {
@@ -139,20 +279,64 @@ pub struct AugmentBalanceInfo {
 pub struct AugmentClient {
     client: Client,
     session_cookie: String,
+    base_url: String,
+    retry: RetryPolicy,
+    /// Requests in progress by URL and whether they may use the cache, so concurrent identical
+    /// GETs share one response
+    in_flight: Mutex<HashMap<(String, bool), InFlight>>,
+    /// Latest schema drift seen per endpoint
+    diagnostics: Mutex<HashMap<String, DecodeDiagnostic>>,
+    cache: HttpCache,
+    /// Records or replays traffic when started in fixture mode
+    fixtures: Arc<FixtureStore>,
+}
+
+type InFlight = Arc<OnceCell<AppResult<Arc<String>>>>;
+
+/// Outcome of a single GET, which may have been conditional
+enum Fetched {
+    Body { body: String, etag: Option<String>, last_modified: Option<String> },
+    NotModified,
 }
 
 impl AugmentClient {
-    pub fn new(session_cookie: String) -> AppResult<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+    pub fn new(
+        session_cookie: String,
+        endpoints: &AugmentEndpoints,
+        retry: &RetryPolicy,
+        network: &NetworkSettings,
+    ) -> AppResult<Self> {
+        let client = network.client_builder()?
+            .timeout(retry.request_timeout())
             .build()?;
 
         Ok(Self {
             client,
             session_cookie,
+            base_url: endpoints.api_base_url.trim_end_matches('/').to_string(),
+            retry: retry.clone(),
+            in_flight: Mutex::new(HashMap::new()),
+            diagnostics: Mutex::new(HashMap::new()),
+            cache: HttpCache::with_file(&HttpCacheSettings::default(), None),
+            fixtures: Arc::new(FixtureStore::off()),
         })
     }
 
+    pub fn with_fixtures(mut self, fixtures: Arc<FixtureStore>) -> Self {
+        self.fixtures = fixtures;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Drop cached responses, including the on-disk copy
+    pub fn purge_cache(&self) {
+        self.cache.purge();
+    }
+
     fn build_headers(&self) -> AppResult<HeaderMap> {
         let mut headers = HeaderMap::new();
         
@@ -171,67 +355,217 @@ impl AugmentClient {
         Ok(headers)
     }
 
+    /// GET `url` and decode the JSON body, retrying transient failures per the retry policy.
+    /// `cache_as` names the cache TTL that applies; `None` always goes to the server.
+    async fn get_json<T: DeserializeOwned>(&self, url: &str, endpoint: &str, cache_as: Option<CachedEndpoint>) -> AppResult<T> {
+        let body = self.get_coalesced(url, endpoint, cache_as).await?;
+        serde_json::from_str(&body)
+            .map_err(|e| AppError::BadPayload(format!("{} API: {}", endpoint, e)))
+    }
+
+    /// GET and decode a response with a known schema. Drift is logged and recorded even when
+    /// decoding still succeeds; the raw payload is kept when it doesn't.
+    async fn get_checked<T: DeserializeOwned + ExpectedSchema>(&self, url: &str, endpoint: &str, cache_as: Option<CachedEndpoint>) -> AppResult<T> {
+        let body = self.get_coalesced(url, endpoint, cache_as).await?;
+
+        let payload: serde_json::Value = match serde_json::from_str(&body) {
+            Ok(payload) => payload,
+            Err(e) => {
+                self.record_diagnostic(DecodeDiagnostic::new(endpoint, Default::default(), Some(e.to_string()), Some(&body)));
+                return Err(AppError::BadPayload(format!("{} API: {}", endpoint, e)));
+            }
+        };
+
+        let drift = schema_drift::detect(&payload, T::FIELDS);
+        match serde_json::from_value::<T>(payload) {
+            Ok(value) => {
+                if !drift.is_empty() {
+                    tracing::warn!("🧬 {} API schema drift: {}", endpoint, drift.summary());
+                    self.record_diagnostic(DecodeDiagnostic::new(endpoint, drift, None, None));
+                }
+                Ok(value)
+            }
+            Err(e) => {
+                let message = format!("{} API: {} ({})", endpoint, e, drift.summary());
+                tracing::error!("❌ Failed to decode {}", message);
+                self.record_diagnostic(DecodeDiagnostic::new(endpoint, drift, Some(e.to_string()), Some(&body)));
+                Err(AppError::BadPayload(message))
+            }
+        }
+    }
+
+    fn record_diagnostic(&self, diagnostic: DecodeDiagnostic) {
+        let mut diagnostics = self.diagnostics.lock().unwrap_or_else(|e| e.into_inner());
+        diagnostics.insert(diagnostic.endpoint.clone(), diagnostic);
+    }
+
+    /// Schema drift and decode failures seen so far, newest first
+    pub fn schema_diagnostics(&self) -> Vec<DecodeDiagnostic> {
+        let diagnostics = self.diagnostics.lock().unwrap_or_else(|e| e.into_inner());
+        let mut diagnostics: Vec<DecodeDiagnostic> = diagnostics.values().cloned().collect();
+        diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.observed_at));
+        diagnostics
+    }
+
+    /// Join an identical request that is already running, or start one others can join
+    async fn get_coalesced(&self, url: &str, endpoint: &str, cache_as: Option<CachedEndpoint>) -> AppResult<Arc<String>> {
+        // A request that must bypass the cache can't share a response served from it
+        let key = (url.to_string(), cache_as.is_some());
+        let cell = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.get(&key) {
+                // A finished cell is stale; only running requests are shared
+                Some(cell) if !cell.initialized() => {
+                    tracing::info!("🔗 Joining in-flight {} request", endpoint);
+                    cell.clone()
+                }
+                _ => {
+                    let cell = InFlight::default();
+                    in_flight.insert(key.clone(), cell.clone());
+                    cell
+                }
+            }
+        };
+
+        let result = cell
+            .get_or_init(|| async {
+                self.get_body(url, endpoint, cache_as).await.map(Arc::new)
+            })
+            .await;
+
+        {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+                in_flight.remove(&key);
+            }
+        }
+
+        match result {
+            Ok(body) => Ok(body.clone()),
+            Err(e) => Err(e.duplicate()),
+        }
+    }
+
+    /// Body of `url`, served from the cache while fresh and revalidated with ETag /
+    /// Last-Modified once stale
+    async fn get_body(&self, url: &str, endpoint: &str, cache_as: Option<CachedEndpoint>) -> AppResult<String> {
+        let Some(ttl) = cache_as.and_then(|cached| self.cache.ttl_for(cached)) else {
+            return match self.retry.run(endpoint, || self.get_body_once(url, endpoint, None)).await? {
+                Fetched::Body { body, .. } => Ok(body),
+                Fetched::NotModified => Err(AppError::BadPayload(format!("{} API: unexpected 304", endpoint))),
+            };
+        };
+
+        let cached = self.cache.get(url);
+        if let Some(entry) = &cached {
+            if entry.is_fresh(ttl, chrono::Utc::now()) {
+                tracing::info!("💾 {} served from cache", endpoint);
+                return Ok(entry.body.clone());
+            }
+        }
+
+        let validators = cached.as_ref().filter(|entry| entry.can_revalidate());
+        match self.retry.run(endpoint, || self.get_body_once(url, endpoint, validators)).await? {
+            Fetched::NotModified => {
+                tracing::info!("♻️ {} not modified, cache revalidated", endpoint);
+                match self.cache.revalidated(url, chrono::Utc::now()).await {
+                    Some(entry) => Ok(entry.body),
+                    None => Err(AppError::BadPayload(format!("{} API: 304 without a cached response", endpoint))),
+                }
+            }
+            Fetched::Body { body, etag, last_modified } => {
+                self.cache.store(url, CachedResponse {
+                    body: body.clone(),
+                    etag,
+                    last_modified,
+                    fetched_at: chrono::Utc::now(),
+                }).await;
+                Ok(body)
+            }
+        }
+    }
+
+    /// Cached value for `url` without touching the network, with how fresh it is
+    fn peek<T: DeserializeOwned>(&self, url: &str, endpoint: CachedEndpoint) -> Option<(T, Freshness)> {
+        let ttl = self.cache.ttl_for(endpoint)?;
+        let entry = self.cache.get(url)?;
+        let value = serde_json::from_str(&entry.body).ok()?;
+        Some((value, Freshness::cached(&entry, ttl, chrono::Utc::now())))
+    }
+
+    /// Single GET attempt, conditional if `validators` is given; failures come back as typed errors
+    async fn get_body_once(&self, url: &str, endpoint: &str, validators: Option<&CachedResponse>) -> AppResult<Fetched> {
+        let mut headers = self.build_headers()?;
+        if let Some(cached) = validators {
+            if let Some(etag) = cached.etag.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = cached.last_modified.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let response = self.fixtures.send(url, self.client.get(url).headers(headers)).await?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED && validators.is_some() {
+            return Ok(Fetched::NotModified);
+        }
+        if !status.is_success() {
+            let retry_after_secs = response.retry_after.as_deref()
+                .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+            tracing::error!("❌ {} API error: {} - {}", endpoint, status, response.body.chars().take(200).collect::<String>());
+            return Err(status_error(status, retry_after_secs, endpoint));
+        }
+
+        Ok(Fetched::Body {
+            body: response.body,
+            etag: response.etag,
+            last_modified: response.last_modified,
+        })
+    }
+
     /// Fetch current credits balance
     pub async fn fetch_credits(&self) -> AppResult<CreditsResponse> {
-        let url = format!("{}/api/credits", AUGMENT_BASE_URL);
+        let url = format!("{}/api/credits", self.base_url);
         tracing::info!("🔄 Fetching credits from: {}", url);
 
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            tracing::error!("❌ Credits API error: {} - {}", status, body);
-            return Err(AppError::Auth(format!("API error: {} - Session may have expired", status)));
-        }
-
-        let credits: CreditsResponse = response.json().await?;
+        let credits: CreditsResponse = self.get_json(&url, "Credits", Some(CachedEndpoint::Credits)).await?;
         tracing::info!("✅ Credits fetched: {} remaining", credits.usage_units_remaining);
         Ok(credits)
     }
 
     /// Fetch subscription info
     pub async fn fetch_subscription(&self) -> AppResult<SubscriptionResponse> {
-        let url = format!("{}/api/subscription", AUGMENT_BASE_URL);
+        let url = format!("{}/api/subscription", self.base_url);
         tracing::info!("🔄 Fetching subscription from: {}", url);
 
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("Subscription API error: {}", status)));
-        }
-
-        let subscription: SubscriptionResponse = response.json().await?;
+        let subscription: SubscriptionResponse = self.get_checked(&url, "Subscription", Some(CachedEndpoint::Subscription)).await?;
         tracing::info!("✅ Subscription fetched: {}", subscription.plan_name);
         Ok(subscription)
     }
 
+    /// Cached subscription, if any, without a request; `Freshness::stale` tells when to refresh
+    pub fn cached_subscription(&self) -> Option<(SubscriptionResponse, Freshness)> {
+        self.peek(&format!("{}/api/subscription", self.base_url), CachedEndpoint::Subscription)
+    }
+
     /// Fetch user info
     pub async fn fetch_user(&self) -> AppResult<UserResponse> {
-        let url = format!("{}/api/user", AUGMENT_BASE_URL);
-        tracing::info!("🔄 Fetching user from: {}", url);
+        self.get_user(Some(CachedEndpoint::User)).await
+    }
 
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
+    /// Fetch user info from the server even when a cached copy is fresh. Validating a session
+    /// must not be answered from the cache.
+    pub async fn fetch_user_uncached(&self) -> AppResult<UserResponse> {
+        self.get_user(None).await
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("User API error: {}", status)));
-        }
+    async fn get_user(&self, cache_as: Option<CachedEndpoint>) -> AppResult<UserResponse> {
+        let url = format!("{}/api/user", self.base_url);
+        tracing::info!("🔄 Fetching user from: {}", url);
 
-        let user: UserResponse = response.json().await?;
+        let user: UserResponse = self.get_checked(&url, "User", cache_as).await?;
         tracing::info!("✅ User fetched: {}", user.email);
         Ok(user)
     }
@@ -267,122 +601,83 @@ impl AugmentClient {
 
         let url = format!(
             "{}/api/credit-analytics-info?startDateIso={}&endDateIso={}",
-            AUGMENT_BASE_URL,
+            self.base_url,
             urlencoding::encode(&start_iso),
             urlencoding::encode(&end_iso)
         );
         tracing::info!("🔄 Fetching credit analytics info from: {}", url);
 
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("Credit analytics API error: {}", status)));
-        }
-
-        let analytics: CreditAnalyticsInfoResponse = response.json().await?;
+        let analytics: CreditAnalyticsInfoResponse = self.get_json(&url, "Credit analytics", None).await?;
         tracing::info!("✅ Credit analytics fetched: {} total consumed", analytics.total_credits_consumed);
         Ok(analytics)
     }
 
-    /// Fetch daily credit consumption (groupBy=NONE, granularity=DAY)
-    pub async fn fetch_daily_consumption(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
-        let end_date = chrono::Utc::now();
-        let start_date = end_date - chrono::Duration::days(days as i64);
-
-        let start_iso = start_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
-        let end_iso = end_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
-
-        let url = format!(
-            "{}/api/credit-consumption?groupBy=NONE&granularity=DAY&startDateIso={}&endDateIso={}",
-            AUGMENT_BASE_URL,
-            urlencoding::encode(&start_iso),
-            urlencoding::encode(&end_iso)
-        );
-        tracing::info!("🔄 Fetching daily consumption from: {}", url);
-
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("Daily consumption API error: {}", status)));
+    /// Fetch credit consumption for any range, grouping and granularity. Ranges longer than
+    /// MAX_CHUNK_DAYS are fetched in chunks and merged.
+    pub async fn fetch_consumption(&self, query: &ConsumptionQuery) -> AppResult<CreditConsumptionResponse> {
+        query.validate()?;
+        let endpoint = format!("{} {} consumption", query.granularity.as_str(), query.group_by.as_str());
+
+        let chunks = query.chunks();
+        let mut responses = Vec::with_capacity(chunks.len());
+        for (start, end) in chunks {
+            let url = query.url(&self.base_url, start, end);
+            tracing::info!("🔄 Fetching {} from: {}", endpoint, url);
+            responses.push(self.get_json::<CreditConsumptionResponse>(&url, &endpoint, None).await?);
         }
 
-        let consumption: CreditConsumptionResponse = response.json().await?;
-        tracing::info!("✅ Daily consumption fetched: {} data points", consumption.data_points.len());
+        let consumption = query.merge(responses);
+        tracing::info!("✅ {} fetched: {} data points", endpoint, consumption.data_points.len());
         Ok(consumption)
     }
 
+    /// Fetch daily credit consumption (groupBy=NONE, granularity=DAY)
+    pub async fn fetch_daily_consumption(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
+        self.fetch_consumption(&ConsumptionQuery::last_days(days, GroupBy::None, Granularity::Day)).await
+    }
+
     /// Fetch consumption by model (groupBy=MODEL_NAME, granularity=TOTAL)
     pub async fn fetch_consumption_by_model(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
-        let end_date = chrono::Utc::now();
-        let start_date = end_date - chrono::Duration::days(days as i64);
-
-        let start_iso = start_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
-        let end_iso = end_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
-
-        let url = format!(
-            "{}/api/credit-consumption?groupBy=MODEL_NAME&granularity=TOTAL&startDateIso={}&endDateIso={}",
-            AUGMENT_BASE_URL,
-            urlencoding::encode(&start_iso),
-            urlencoding::encode(&end_iso)
-        );
-        tracing::info!("🔄 Fetching consumption by model from: {}", url);
-
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("Model consumption API error: {}", status)));
-        }
-
-        let consumption: CreditConsumptionResponse = response.json().await?;
-        tracing::info!("✅ Model consumption fetched: {} models", consumption.data_points.len());
-        Ok(consumption)
+        self.fetch_consumption(&ConsumptionQuery::last_days(days, GroupBy::ModelName, Granularity::Total)).await
     }
 
     /// Fetch consumption by activity type (groupBy=ACTIVITY_TYPE, granularity=TOTAL)
     pub async fn fetch_consumption_by_activity(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
-        let end_date = chrono::Utc::now();
-        let start_date = end_date - chrono::Duration::days(days as i64);
-
-        let start_iso = start_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
-        let end_iso = end_date.format("%Y-%m-%dT00:00:00.000Z").to_string();
+        self.fetch_consumption(&ConsumptionQuery::last_days(days, GroupBy::ActivityType, Granularity::Total)).await
+    }
 
-        let url = format!(
-            "{}/api/credit-consumption?groupBy=ACTIVITY_TYPE&granularity=TOTAL&startDateIso={}&endDateIso={}",
-            AUGMENT_BASE_URL,
-            urlencoding::encode(&start_iso),
-            urlencoding::encode(&end_iso)
-        );
-        tracing::info!("🔄 Fetching consumption by activity from: {}", url);
+    /// Fetch per-day consumption by model (groupBy=MODEL_NAME, granularity=DAY)
+    pub async fn fetch_daily_consumption_by_model(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
+        self.fetch_consumption(&ConsumptionQuery::last_days(days, GroupBy::ModelName, Granularity::Day)).await
+    }
 
-        let response = self.client
-            .get(&url)
-            .headers(self.build_headers()?)
-            .send()
-            .await?;
+    /// Fetch per-day consumption by activity type (groupBy=ACTIVITY_TYPE, granularity=DAY)
+    pub async fn fetch_daily_consumption_by_activity(&self, days: u32) -> AppResult<CreditConsumptionResponse> {
+        self.fetch_consumption(&ConsumptionQuery::last_days(days, GroupBy::ActivityType, Granularity::Day)).await
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            return Err(AppError::Auth(format!("Activity consumption API error: {}", status)));
-        }
+    /// Convert grouped per-day consumption to (date, group, credits) rows
+    pub fn to_daily_group_usage(&self, consumption: &CreditConsumptionResponse) -> Vec<DailyGroupUsage> {
+        consumption.data_points.iter()
+            .filter_map(|dp| {
+                let credits = dp.credits_consumed.as_ref()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .unwrap_or(0);
 
-        let consumption: CreditConsumptionResponse = response.json().await?;
-        tracing::info!("✅ Activity consumption fetched: {} types", consumption.data_points.len());
-        Ok(consumption)
+                if credits > 0 {
+                    let date = dp.date_range.start_date_iso.split('T').next()
+                        .unwrap_or(&dp.date_range.start_date_iso)
+                        .to_string();
+                    dp.group_key.as_ref().map(|group_key| DailyGroupUsage {
+                        date,
+                        group_key: group_key.clone(),
+                        credits,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
     }
 
     /// Convert consumption response to daily usage list
@@ -448,11 +743,319 @@ impl AugmentClient {
 
     /// Validate session cookie by testing the API
     pub async fn validate_session(&self) -> AppResult<bool> {
-        match self.fetch_user().await {
+        match self.fetch_user_uncached().await {
             Ok(_) => Ok(true),
-            Err(AppError::Auth(_)) => Ok(false),
+            Err(e) if e.is_auth_failure() || matches!(e, AppError::Forbidden(_)) => Ok(false),
             Err(e) => Err(e),
         }
     }
 }
 
+
+
+/// One long-lived client per account, so connections and TLS sessions are reused. It is rebuilt
+/// only when the session cookie or one of the client settings change.
+pub struct AugmentClientCache {
+    current: Option<(ClientKey, Arc<AugmentClient>)>,
+    fixtures: Arc<FixtureStore>,
+}
+
+#[derive(PartialEq)]
+struct ClientKey {
+    session_cookie: String,
+    endpoints: AugmentEndpoints,
+    retry: RetryPolicy,
+    cache: HttpCacheSettings,
+    network: NetworkSettings,
+}
+
+impl AugmentClientCache {
+    pub fn new(fixtures: Arc<FixtureStore>) -> Self {
+        Self { current: None, fixtures }
+    }
+
+    pub fn get(
+        &mut self,
+        session_cookie: &str,
+        endpoints: &AugmentEndpoints,
+        retry: &RetryPolicy,
+        cache: &HttpCacheSettings,
+        network: &NetworkSettings,
+    ) -> AppResult<Arc<AugmentClient>> {
+        let key = ClientKey {
+            session_cookie: session_cookie.to_string(),
+            endpoints: endpoints.clone(),
+            retry: retry.clone(),
+            cache: cache.clone(),
+            network: network.clone(),
+        };
+
+        if let Some((current_key, client)) = &self.current {
+            if *current_key == key {
+                return Ok(client.clone());
+            }
+        }
+
+        tracing::info!("🔧 Creating Augment client for {}", endpoints.api_base_url);
+        let client = AugmentClient::new(key.session_cookie.clone(), endpoints, retry, network)?
+            .with_cache(HttpCache::new(cache, session_cookie))
+            .with_fixtures(self.fixtures.clone());
+        let client = Arc::new(client);
+        self.current = Some((key, client.clone()));
+        Ok(client)
+    }
+
+    /// Forget the client and its cached responses, e.g. on logout
+    pub fn clear(&mut self) {
+        if let Some((_, client)) = self.current.take() {
+            client.purge_cache();
+        }
+    }
+}
+
+/// Error for a non-2xx response from `endpoint`
+fn status_error(status: StatusCode, retry_after_secs: Option<u64>, endpoint: &str) -> AppError {
+    let message = format!("{} API returned {}", endpoint, status);
+    match status {
+        StatusCode::UNAUTHORIZED => AppError::Unauthorized(message),
+        StatusCode::FORBIDDEN => AppError::Forbidden(message),
+        StatusCode::TOO_MANY_REQUESTS => AppError::RateLimit { retry_after_secs },
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => AppError::Timeout,
+        status if status.is_server_error() => AppError::Server {
+            status: status.as_u16(),
+            message,
+            retry_after_secs,
+        },
+        _ => AppError::Unknown(message),
+    }
+}
+
+/// `Retry-After` as seconds from `now`; the header holds either seconds or an HTTP date
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+    chrono::DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| (date.with_timezone(&chrono::Utc) - now).num_seconds().max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_origin_follows_configured_host() {
+        let endpoints = AugmentEndpoints {
+            api_base_url: "http://127.0.0.1:8080".to_string(),
+            login_url: "https://acme.augmentcode.com/login".to_string(),
+        };
+        assert!(endpoints.validate().is_ok());
+
+        let parse = |url: &str| url::Url::parse(url).unwrap();
+        assert!(endpoints.is_login_origin(&parse("https://acme.augmentcode.com/__tauri_extract_session__")));
+        assert!(!endpoints.is_login_origin(&parse("https://app.augmentcode.com/__tauri_extract_session__")));
+        assert!(!endpoints.is_login_origin(&parse("http://acme.augmentcode.com/")));
+
+        let invalid = AugmentEndpoints { api_base_url: "ftp://example.com".to_string(), ..endpoints };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_status_errors_are_typed() {
+        assert!(status_error(StatusCode::UNAUTHORIZED, None, "Credits").is_auth_failure());
+        assert!(matches!(status_error(StatusCode::FORBIDDEN, None, "Credits"), AppError::Forbidden(_)));
+        assert!(matches!(
+            status_error(StatusCode::TOO_MANY_REQUESTS, Some(30), "Credits"),
+            AppError::RateLimit { retry_after_secs: Some(30) }
+        ));
+        assert!(matches!(
+            status_error(StatusCode::BAD_GATEWAY, None, "Credits"),
+            AppError::Server { status: 502, .. }
+        ));
+        assert!(!status_error(StatusCode::INTERNAL_SERVER_ERROR, None, "Credits").is_auth_failure());
+    }
+
+    /// HTTP server that answers every request with `body` after `delay`; returns the request count
+    async fn serve_json(body: &'static str, delay: std::time::Duration) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = socket.read(&mut buf).await;
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_are_coalesced() {
+        let (url, requests) = serve_json(
+            r#"{"usageUnitsAvailable":10,"usageUnitsUsedThisBillingCycle":5,"usageUnitsRemaining":10,"usageUnitsConsumedThisBillingCycle":5}"#,
+            std::time::Duration::from_millis(200),
+        ).await;
+        let endpoints = AugmentEndpoints { api_base_url: url, ..AugmentEndpoints::default() };
+
+        let mut cache = AugmentClientCache::new(Arc::new(FixtureStore::off()));
+        let client = cache.get("cookie", &endpoints, &RetryPolicy::default(), &HttpCacheSettings::default(), &NetworkSettings::default()).unwrap();
+        assert!(Arc::ptr_eq(&client, &cache.get("cookie", &endpoints, &RetryPolicy::default(), &HttpCacheSettings::default(), &NetworkSettings::default()).unwrap()));
+
+        let (first, second) = tokio::join!(client.fetch_credits(), client.fetch_credits());
+        assert_eq!(first.unwrap().usage_units_remaining, 10);
+        assert_eq!(second.unwrap().usage_units_remaining, 10);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Finished requests are not reused
+        client.fetch_credits().await.unwrap();
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let other = cache.get("new-cookie", &endpoints, &RetryPolicy::default(), &HttpCacheSettings::default(), &NetworkSettings::default()).unwrap();
+        assert!(!Arc::ptr_eq(&client, &other));
+    }
+
+    /// Server that tags its response with an ETag and answers 304 when it is sent back
+    async fn serve_with_etag(body: &'static str) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let read = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..read]).to_lowercase();
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\nconnection: close\r\n\r\n".to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\netag: \"v1\"\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_cached_responses_are_reused_and_revalidated() {
+        let (url, requests) = serve_with_etag(r#"{"planId":"dev","planName":"Developer","billingPeriodEnd":"2025-12-01T00:00:00Z"}"#).await;
+        let endpoints = AugmentEndpoints { api_base_url: url.clone(), ..AugmentEndpoints::default() };
+        let client = AugmentClient::new("cookie".to_string(), &endpoints, &RetryPolicy::default(), &NetworkSettings::default()).unwrap();
+        assert!(client.cached_subscription().is_none());
+
+        client.fetch_subscription().await.unwrap();
+        client.fetch_subscription().await.unwrap();
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let (subscription, freshness) = client.cached_subscription().unwrap();
+        assert_eq!(subscription.plan_name, "Developer");
+        assert!(freshness.from_cache && !freshness.stale);
+
+        // A stale entry is revalidated instead of downloaded again
+        let cache = HttpCache::with_file(&HttpCacheSettings::default(), None);
+        let subscription_url = format!("{}/api/subscription", url);
+        let mut stale = cache_entry(&client, &subscription_url);
+        stale.fetched_at -= chrono::Duration::hours(2);
+        stale.body = stale.body.replace("Developer", "Cached");
+        cache.store(&subscription_url, stale).await;
+        let client = AugmentClient::new("cookie".to_string(), &endpoints, &RetryPolicy::default(), &NetworkSettings::default()).unwrap().with_cache(cache);
+        assert!(client.cached_subscription().unwrap().1.stale);
+
+        // 304: the cached body is kept
+        assert_eq!(client.fetch_subscription().await.unwrap().plan_name, "Cached");
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(!client.cached_subscription().unwrap().1.stale);
+    }
+
+    #[tokio::test]
+    async fn test_session_validation_bypasses_cache() {
+        let (url, requests) = serve_json(r#"{"email":"a@b.c"}"#, std::time::Duration::ZERO).await;
+        let endpoints = AugmentEndpoints { api_base_url: url, ..AugmentEndpoints::default() };
+        let client = AugmentClient::new("cookie".to_string(), &endpoints, &RetryPolicy::default(), &NetworkSettings::default()).unwrap();
+
+        client.fetch_user().await.unwrap();
+        client.fetch_user().await.unwrap();
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        assert!(client.validate_session().await.unwrap());
+        assert_eq!(client.fetch_user_uncached().await.unwrap().email, "a@b.c");
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    fn cache_entry(client: &AugmentClient, url: &str) -> CachedResponse {
+        client.cache.get(url).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_subscription_survives_schema_drift() {
+        let (url, _) = serve_json(
+            r#"{"planId":"dev","planName":"Developer","billingPeriodEnd":"2025-12-01T00:00:00Z","trialGrant":"500","seatLimit":5}"#,
+            std::time::Duration::ZERO,
+        ).await;
+        let endpoints = AugmentEndpoints { api_base_url: url, ..AugmentEndpoints::default() };
+        let client = AugmentClient::new("cookie".to_string(), &endpoints, &RetryPolicy::default(), &NetworkSettings::default()).unwrap();
+
+        let subscription = client.fetch_subscription().await.unwrap();
+        assert_eq!(subscription.plan_name, "Developer");
+        assert_eq!(subscription.trial_grant, 0);
+
+        let diagnostics = client.schema_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].drift.missing.contains(&"planFacts".to_string()));
+        assert_eq!(diagnostics[0].drift.unexpected, vec!["seatLimit"]);
+        assert_eq!(diagnostics[0].drift.retyped[0].field, "trialGrant");
+        assert!(diagnostics[0].raw_payload.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_missing_essential_field_keeps_raw_payload() {
+        let (url, _) = serve_json(r#"{"emailAddress":"a@b.c"}"#, std::time::Duration::ZERO).await;
+        let endpoints = AugmentEndpoints { api_base_url: url, ..AugmentEndpoints::default() };
+        let client = AugmentClient::new("cookie".to_string(), &endpoints, &RetryPolicy::default(), &NetworkSettings::default()).unwrap();
+
+        let error = client.fetch_user().await.unwrap_err();
+        assert!(matches!(&error, AppError::BadPayload(message) if message.contains("missing: email")));
+
+        let diagnostics = client.schema_diagnostics();
+        assert_eq!(diagnostics[0].endpoint, "User");
+        assert_eq!(diagnostics[0].raw_payload.as_deref(), Some(r#"{"emailAddress":"a@b.c"}"#));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&chrono::Utc);
+        assert_eq!(parse_retry_after("120", now), Some(120));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:29:30 GMT", now), Some(90));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(0));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -0,0 +1,240 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::augment_client::{AugmentClient, DailyUsage};
+use crate::database::{Database, DailyConsumptionRecord, SyntheticBalancePoint};
+use crate::error::AppResult;
+
+/// Lookback used when the subscription doesn't tell us how far back consumption data goes
+const DEFAULT_BACKFILL_DAYS: u32 = 90;
+
+const TOTAL_STEPS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStage {
+    FetchingSubscription,
+    FetchingConsumption,
+    FetchingCredits,
+    Reconstructing,
+    Storing,
+    Completed,
+    Failed,
+}
+
+/// Progress payload emitted to the frontend as `backfill-progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub stage: BackfillStage,
+    pub step: u32,
+    pub total_steps: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillSummary {
+    pub days_requested: u32,
+    pub days_with_usage: usize,
+    pub points_inserted: usize,
+    pub oldest_point: Option<DateTime<Utc>>,
+}
+
+fn report<F: Fn(BackfillProgress)>(progress: &F, stage: BackfillStage, step: u32, message: String) {
+    progress(BackfillProgress {
+        stage,
+        step,
+        total_steps: TOTAL_STEPS,
+        message,
+    });
+}
+
+/// Rebuild an approximate balance history from server-side daily consumption.
+///
+/// Previously reconstructed (synthetic) records are replaced; live samples are never touched
+/// and only points older than the first live sample are written.
+pub async fn run_backfill<F>(
+    client: &AugmentClient,
+    database: &Database,
+    max_days: u32,
+    progress: F,
+) -> AppResult<BackfillSummary>
+where
+    F: Fn(BackfillProgress),
+{
+    let result = backfill_steps(client, database, max_days, &progress).await;
+
+    match &result {
+        Ok(summary) => report(
+            &progress,
+            BackfillStage::Completed,
+            TOTAL_STEPS,
+            format!("Reconstructed {} balance points", summary.points_inserted),
+        ),
+        Err(e) => report(&progress, BackfillStage::Failed, TOTAL_STEPS, e.to_string()),
+    }
+
+    result
+}
+
+async fn backfill_steps<F>(
+    client: &AugmentClient,
+    database: &Database,
+    max_days: u32,
+    progress: &F,
+) -> AppResult<BackfillSummary>
+where
+    F: Fn(BackfillProgress),
+{
+    let today = Utc::now().date_naive();
+
+    // Step 1: find out how far back the server keeps consumption data
+    report(progress, BackfillStage::FetchingSubscription, 1, "Checking available history range".to_string());
+    let days = match client.fetch_subscription().await {
+        Ok(subscription) => subscription.credit_consumption_min_date
+            .as_deref()
+            .and_then(parse_iso_date)
+            .map(|min_date| ((today - min_date).num_days().max(1) as u32).min(max_days))
+            .unwrap_or_else(|| DEFAULT_BACKFILL_DAYS.min(max_days)),
+        Err(e) => {
+            tracing::warn!("⚠️ Backfill: could not fetch subscription, using default range: {}", e);
+            DEFAULT_BACKFILL_DAYS.min(max_days)
+        }
+    };
+
+    // Step 2: daily consumption for the whole range
+    report(progress, BackfillStage::FetchingConsumption, 2, format!("Fetching {} days of consumption", days));
+    let consumption = client.fetch_daily_consumption(days).await?;
+    let daily_usage = client.to_daily_usage(&consumption);
+
+    let now = Utc::now();
+    let consumption_records: Vec<DailyConsumptionRecord> = daily_usage.iter()
+        .map(|d| DailyConsumptionRecord {
+            date: d.date.clone(),
+            credits: d.total_credits,
+            updated_at: now,
+        })
+        .collect();
+    database.upsert_daily_consumption(&consumption_records).await?;
+
+    // Step 3: current balance anchors the reconstruction
+    report(progress, BackfillStage::FetchingCredits, 3, "Fetching current balance".to_string());
+    let credits = client.fetch_credits().await?;
+
+    // Step 4: walk backwards from today's balance
+    report(progress, BackfillStage::Reconstructing, 4, "Reconstructing daily balances".to_string());
+    let mut points = reconstruct_balance_history(credits.usage_units_remaining, &daily_usage, today);
+
+    if let Some(earliest_live) = database.get_earliest_live_balance_timestamp().await? {
+        points.retain(|p| p.timestamp < earliest_live);
+    }
+
+    // Step 5: persist
+    report(progress, BackfillStage::Storing, 5, format!("Storing {} balance points", points.len()));
+    database.delete_synthetic_records().await?;
+    let points_inserted = database.insert_synthetic_history(&points).await?;
+
+    tracing::info!("✅ Backfill complete: {} points over {} days", points_inserted, days);
+
+    Ok(BackfillSummary {
+        days_requested: days,
+        days_with_usage: daily_usage.len(),
+        points_inserted,
+        oldest_point: points.first().map(|p| p.timestamp),
+    })
+}
+
+/// Reconstruct one balance point per UTC midnight, ending with today's midnight.
+///
+/// Today's midnight is `remaining` plus what was consumed since then (today's bucket in
+/// `daily_usage`), and each earlier midnight is the later balance plus that day's consumption.
+/// Top-ups or cycle renewals are not visible, so the result is only an approximation.
+pub fn reconstruct_balance_history(
+    remaining: i64,
+    daily_usage: &[DailyUsage],
+    today: NaiveDate,
+) -> Vec<SyntheticBalancePoint> {
+    let by_date: HashMap<NaiveDate, i64> = daily_usage.iter()
+        .filter_map(|d| parse_iso_date(&d.date).map(|date| (date, d.total_credits.max(0))))
+        .filter(|(date, _)| *date <= today)
+        .collect();
+
+    let earliest = match by_date.keys().filter(|date| **date < today).min() {
+        Some(date) => *date,
+        None => return Vec::new(),
+    };
+
+    // Walk backwards from today, accumulating consumption
+    let mut points = Vec::new();
+    let mut balance = remaining.max(0) + by_date.get(&today).copied().unwrap_or(0);
+    let mut day = today;
+
+    while day >= earliest {
+        let consumed_previous_day = by_date.get(&(day - Duration::days(1))).copied().unwrap_or(0);
+        points.push(SyntheticBalancePoint {
+            timestamp: midnight_utc(day),
+            amount: balance.min(u32::MAX as i64) as u32,
+            usage_since_previous: (day > earliest)
+                .then(|| consumed_previous_day.min(u32::MAX as i64) as u32),
+        });
+        balance += consumed_previous_day;
+        day -= Duration::days(1);
+    }
+
+    points.reverse();
+    points
+}
+
+fn parse_iso_date(value: &str) -> Option<NaiveDate> {
+    let date_part = value.split('T').next().unwrap_or(value);
+    NaiveDate::parse_from_str(date_part, "%Y-%m-%d").ok()
+}
+
+fn midnight_utc(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight is always a valid time")
+        .and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(date: &str, credits: i64) -> DailyUsage {
+        DailyUsage { date: date.to_string(), total_credits: credits }
+    }
+
+    #[test]
+    fn test_reconstruct_balance_history() {
+        let today = NaiveDate::from_ymd_opt(2025, 11, 10).unwrap();
+        let daily = vec![usage("2025-11-07", 100), usage("2025-11-09", 50)];
+
+        let points = reconstruct_balance_history(1000, &daily, today);
+        let amounts: Vec<u32> = points.iter().map(|p| p.amount).collect();
+
+        // 11-07, 11-08, 11-09, 11-10 midnights
+        assert_eq!(amounts, vec![1150, 1050, 1050, 1000]);
+        assert_eq!(points[0].usage_since_previous, None);
+        assert_eq!(points[1].usage_since_previous, Some(100));
+        assert_eq!(points[3].usage_since_previous, Some(50));
+        assert_eq!(points[3].timestamp, midnight_utc(today));
+    }
+
+    #[test]
+    fn test_reconstruct_balance_history_adds_todays_usage() {
+        let today = NaiveDate::from_ymd_opt(2025, 11, 10).unwrap();
+        let daily = vec![usage("2025-11-09", 50), usage("2025-11-10", 30)];
+
+        let points = reconstruct_balance_history(1000, &daily, today);
+        let amounts: Vec<u32> = points.iter().map(|p| p.amount).collect();
+
+        // 30 credits were used since today's midnight
+        assert_eq!(amounts, vec![1080, 1030]);
+        assert!(reconstruct_balance_history(1000, &[usage("2025-11-10", 30)], today).is_empty());
+    }
+
+    #[test]
+    fn test_reconstruct_balance_history_without_data() {
+        let today = NaiveDate::from_ymd_opt(2025, 11, 10).unwrap();
+        assert!(reconstruct_balance_history(1000, &[], today).is_empty());
+    }
+}
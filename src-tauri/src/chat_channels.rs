@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use crate::analytics::{AlertInfo, AlertLevel};
+use crate::error::{AppError, AppResult};
+use crate::webhook::WebhookConfig;
+
+/// Chat services with an incoming-webhook format of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatPlatform {
+    /// Block Kit message
+    Slack,
+    /// Embed
+    Discord,
+    /// Adaptive Card
+    Teams,
+}
+
+impl ChatPlatform {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChatPlatform::Slack => "Slack",
+            ChatPlatform::Discord => "Discord",
+            ChatPlatform::Teams => "Microsoft Teams",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChannelConfig {
+    pub id: String,
+    pub name: String,
+    pub platform: ChatPlatform,
+    /// Incoming webhook URL issued by the chat service
+    pub url: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Alert severities delivered to this channel
+    #[serde(default = "default_severities")]
+    pub severities: Vec<AlertLevel>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_severities() -> Vec<AlertLevel> {
+    vec![AlertLevel::Warning, AlertLevel::Critical]
+}
+
+impl ChatChannelConfig {
+    pub fn accepts(&self, level: AlertLevel) -> bool {
+        self.enabled && self.severities.contains(&level)
+    }
+
+    /// Delivery settings for the shared webhook sender; chat services don't verify signatures
+    pub fn as_webhook(&self) -> WebhookConfig {
+        WebhookConfig {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            url: self.url.clone(),
+            enabled: self.enabled,
+            headers: HashMap::new(),
+            secret: None,
+            body_template: None,
+            timeout_seconds: 10,
+            max_retries: 3,
+        }
+    }
+}
+
+pub fn validate_chat_channels(channels: &[ChatChannelConfig]) -> AppResult<()> {
+    let mut ids = std::collections::HashSet::new();
+    for channel in channels {
+        let invalid = |reason: &str| AppError::Config(
+            config::ConfigError::Message(format!("{} channel '{}': {}", channel.platform.label(), channel.id, reason))
+        );
+
+        if channel.id.trim().is_empty() {
+            return Err(invalid("id cannot be empty"));
+        }
+        if !ids.insert(channel.id.as_str()) {
+            return Err(invalid("duplicate channel id"));
+        }
+        match url::Url::parse(&channel.url) {
+            Ok(url) if url.scheme() == "https" => {}
+            _ => return Err(invalid("webhook URL must be a valid https URL")),
+        }
+        if channel.severities.is_empty() {
+            return Err(invalid("select at least one severity"));
+        }
+    }
+    Ok(())
+}
+
+/// An alert as shown in chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatAlert {
+    pub title: String,
+    pub level: AlertLevel,
+    pub message: String,
+    pub balance: Option<u32>,
+    pub hours_to_depletion: Option<f64>,
+    pub portal_url: Option<String>,
+}
+
+impl ChatAlert {
+    pub fn from_alert_info(title: &str, info: &AlertInfo, balance: Option<u32>, portal_url: Option<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            level: info.level,
+            message: info.message.clone(),
+            balance,
+            hours_to_depletion: info.estimated_time_remaining,
+            portal_url,
+        }
+    }
+
+    fn level_label(&self) -> &'static str {
+        match self.level {
+            AlertLevel::Info => "Info",
+            AlertLevel::Warning => "Warning",
+            AlertLevel::Critical => "Critical",
+        }
+    }
+
+    fn level_emoji(&self) -> &'static str {
+        match self.level {
+            AlertLevel::Info => "ℹ️",
+            AlertLevel::Warning => "⚠️",
+            AlertLevel::Critical => "🚨",
+        }
+    }
+
+    fn balance_text(&self) -> String {
+        self.balance
+            .map(|b| format!("{} credits", b))
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    fn depletion_text(&self) -> String {
+        match self.hours_to_depletion {
+            Some(hours) if hours < 48.0 => format!("~{:.1} hours", hours),
+            Some(hours) => format!("~{:.1} days", hours / 24.0),
+            None => "Not depleting".to_string(),
+        }
+    }
+
+    fn facts(&self) -> [(&'static str, String); 3] {
+        [
+            ("Severity", self.level_label().to_string()),
+            ("Balance", self.balance_text()),
+            ("Depletes in", self.depletion_text()),
+        ]
+    }
+}
+
+/// JSON body for the platform's incoming webhook
+pub fn format_message(platform: ChatPlatform, alert: &ChatAlert) -> Value {
+    match platform {
+        ChatPlatform::Slack => slack_blocks(alert),
+        ChatPlatform::Discord => discord_embed(alert),
+        ChatPlatform::Teams => teams_adaptive_card(alert),
+    }
+}
+
+fn slack_blocks(alert: &ChatAlert) -> Value {
+    let fields: Vec<Value> = alert.facts().iter()
+        .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+        .collect();
+
+    let mut blocks = vec![
+        json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": format!("{} {}", alert.level_emoji(), alert.title), "emoji": true }
+        }),
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": alert.message }
+        }),
+        json!({ "type": "section", "fields": fields }),
+    ];
+
+    if let Some(url) = &alert.portal_url {
+        blocks.push(json!({
+            "type": "actions",
+            "elements": [{
+                "type": "button",
+                "text": { "type": "plain_text", "text": "Open billing portal" },
+                "url": url
+            }]
+        }));
+    }
+
+    json!({
+        "text": format!("{} {}: {}", alert.level_emoji(), alert.title, alert.message),
+        "blocks": blocks
+    })
+}
+
+fn discord_embed(alert: &ChatAlert) -> Value {
+    let color = match alert.level {
+        AlertLevel::Info => 0x3498db,
+        AlertLevel::Warning => 0xf1c40f,
+        AlertLevel::Critical => 0xe74c3c,
+    };
+
+    let fields: Vec<Value> = alert.facts().iter()
+        .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+        .collect();
+
+    let mut embed = json!({
+        "title": format!("{} {}", alert.level_emoji(), alert.title),
+        "description": alert.message,
+        "color": color,
+        "fields": fields,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    });
+    if let Some(url) = &alert.portal_url {
+        embed["url"] = json!(url);
+    }
+
+    json!({
+        "username": "orb Credit Monitor",
+        "embeds": [embed]
+    })
+}
+
+fn teams_adaptive_card(alert: &ChatAlert) -> Value {
+    let color = match alert.level {
+        AlertLevel::Info => "Accent",
+        AlertLevel::Warning => "Warning",
+        AlertLevel::Critical => "Attention",
+    };
+
+    let facts: Vec<Value> = alert.facts().iter()
+        .map(|(name, value)| json!({ "title": name, "value": value }))
+        .collect();
+
+    let mut card = json!({
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "type": "AdaptiveCard",
+        "version": "1.4",
+        "body": [
+            { "type": "TextBlock", "text": alert.title, "weight": "Bolder", "size": "Medium", "color": color },
+            { "type": "TextBlock", "text": alert.message, "wrap": true },
+            { "type": "FactSet", "facts": facts }
+        ]
+    });
+    if let Some(url) = &alert.portal_url {
+        card["actions"] = json!([{ "type": "Action.OpenUrl", "title": "Open billing portal", "url": url }]);
+    }
+
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": card
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(portal_url: Option<&str>) -> ChatAlert {
+        ChatAlert {
+            title: "Low Balance Warning".to_string(),
+            level: AlertLevel::Warning,
+            message: "Only 420 credits left".to_string(),
+            balance: Some(420),
+            hours_to_depletion: Some(30.0),
+            portal_url: portal_url.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_slack_blocks() {
+        let body = format_message(ChatPlatform::Slack, &alert(Some("https://portal.example/p")));
+        let blocks = body["blocks"].as_array().unwrap();
+
+        assert_eq!(blocks[0]["type"], "header");
+        assert_eq!(blocks[1]["text"]["text"], "Only 420 credits left");
+        assert_eq!(blocks[2]["fields"][1]["text"], "*Balance*\n420 credits");
+        assert_eq!(blocks[2]["fields"][2]["text"], "*Depletes in*\n~30.0 hours");
+        assert_eq!(blocks[3]["elements"][0]["url"], "https://portal.example/p");
+    }
+
+    #[test]
+    fn test_discord_embed_without_portal() {
+        let body = format_message(ChatPlatform::Discord, &alert(None));
+        let embed = &body["embeds"][0];
+
+        assert_eq!(embed["color"], 0xf1c40f);
+        assert_eq!(embed["fields"][0]["value"], "Warning");
+        assert!(embed.get("url").is_none());
+    }
+
+    #[test]
+    fn test_teams_adaptive_card() {
+        let mut critical = alert(Some("https://portal.example/p"));
+        critical.level = AlertLevel::Critical;
+        critical.hours_to_depletion = Some(72.0);
+
+        let body = format_message(ChatPlatform::Teams, &critical);
+        let card = &body["attachments"][0]["content"];
+
+        assert_eq!(body["attachments"][0]["contentType"], "application/vnd.microsoft.card.adaptive");
+        assert_eq!(card["body"][0]["color"], "Attention");
+        assert_eq!(card["body"][2]["facts"][2]["value"], "~3.0 days");
+        assert_eq!(card["actions"][0]["url"], "https://portal.example/p");
+    }
+
+    #[test]
+    fn test_channel_severity_routing() {
+        let channel = ChatChannelConfig {
+            id: "ops".to_string(),
+            name: "Ops".to_string(),
+            platform: ChatPlatform::Slack,
+            url: "https://hooks.slack.com/services/x".to_string(),
+            enabled: true,
+            severities: vec![AlertLevel::Critical],
+        };
+
+        assert!(channel.accepts(AlertLevel::Critical));
+        assert!(!channel.accepts(AlertLevel::Warning));
+        assert!(validate_chat_channels(&[channel]).is_ok());
+    }
+}
@@ -1,6 +1,20 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use crate::alert_rules::{self, AlertRule};
+use crate::augment_client::AugmentEndpoints;
+use crate::alert_state::AlertBehaviorSettings;
+use crate::chat_channels::{self, ChatChannelConfig};
+use crate::email::EmailSettings;
+use crate::notification_sinks::NotificationSinkSettings;
+use crate::quiet_hours::QuietHoursSettings;
+use crate::retry::RetryPolicy;
+use crate::http_cache::HttpCacheSettings;
+use crate::network::NetworkSettings;
+use crate::sounds::SoundSettings;
+use crate::subscription_watch::SubscriptionAlertSettings;
+use crate::webhook::{self, WebhookConfig};
 use crate::error::{AppError, AppResult};
+use crate::forecast::ForecastMethod;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -17,6 +31,19 @@ pub struct AppConfig {
     pub session_cookie: Option<String>,
     #[serde(default)]
     pub user_email: Option<String>,
+    // API and login origins of the account (enterprise tenant or local mock server)
+    #[serde(default)]
+    pub augment_endpoints: AugmentEndpoints,
+    // Retries, backoff and deadlines for Augment API requests
+    #[serde(default)]
+    pub http_retry: RetryPolicy,
+    // Response caching (TTLs, on-disk copy) for Augment API requests
+    #[serde(default)]
+    pub http_cache: HttpCacheSettings,
+    // Proxy and extra CA certificates for every outbound HTTP client. update_config rebuilds the
+    // Orb scraper live (ScraperHandle::set_network); a failed rebuild keeps the previous scraper
+    #[serde(default)]
+    pub network: NetworkSettings,
 
     // App settings
     pub polling_interval_seconds: u64,
@@ -29,6 +56,95 @@ pub struct AppConfig {
     pub compact_mode: bool,
     pub theme: Theme,
     pub data_retention_days: u32,
+
+    // Forecasting
+    #[serde(default)]
+    pub forecast_method: ForecastMethod,
+    #[serde(default = "default_true")]
+    pub auto_select_forecast_method: bool,
+
+    // Minutes the provider has to be unreachable before a "connection lost" alert
+    #[serde(default = "default_connection_lost_after_minutes")]
+    pub connection_lost_after_minutes: u32,
+
+    // Consecutive auth failures before the session is treated as expired
+    #[serde(default = "default_session_expiry_failures")]
+    pub session_expiry_failures: u32,
+
+    // Model / activity mix change alerts
+    #[serde(default)]
+    pub mix_alerts: MixAlertSettings,
+
+    // User-defined alert rules (empty = defaults built from the balance thresholds)
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+
+    // Hysteresis and escalation margins for the alert state machine
+    #[serde(default)]
+    pub alert_behavior: AlertBehaviorSettings,
+
+    // Outgoing webhooks that alert rules can deliver to
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    // Slack / Discord / Teams incoming webhooks, routed by alert severity
+    #[serde(default)]
+    pub chat_channels: Vec<ChatChannelConfig>,
+
+    // SMTP alerts and scheduled digests
+    #[serde(default)]
+    pub email: EmailSettings,
+
+    // Do-not-disturb schedule for desktop notifications
+    #[serde(default)]
+    pub quiet_hours: QuietHoursSettings,
+
+    // Trial, renewal, payment and plan change notifications
+    #[serde(default)]
+    pub subscription_alerts: SubscriptionAlertSettings,
+
+    // Volume and custom files for sound alerts (used when enable_sound_alerts is on)
+    #[serde(default)]
+    pub sounds: SoundSettings,
+
+    // Desktop-style sinks, and the channels notifications not raised by alert rules go to
+    #[serde(default)]
+    pub notification_sinks: NotificationSinkSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixAlertSettings {
+    pub enabled: bool,
+    /// Days before the checked day that form the rolling baseline
+    pub baseline_days: u32,
+    /// Change in share (percentage points) that counts as a shift
+    pub share_shift_threshold_percent: f64,
+    /// Days with less total usage than this are ignored
+    pub min_daily_credits: i64,
+}
+
+impl Default for MixAlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            baseline_days: 7,
+            share_shift_threshold_percent: 25.0,
+            min_daily_credits: 50,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_session_expiry_failures() -> u32 {
+    3
+}
+
+fn default_connection_lost_after_minutes() -> u32 {
+    5
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +164,10 @@ impl Default for AppConfig {
             // New Augment API fields
             session_cookie: None,
             user_email: None,
+            augment_endpoints: AugmentEndpoints::default(),
+            http_retry: RetryPolicy::default(),
+            http_cache: HttpCacheSettings::default(),
+            network: NetworkSettings::default(),
             // App settings
             polling_interval_seconds: 60,
             low_balance_threshold: 500,
@@ -59,6 +179,23 @@ impl Default for AppConfig {
             compact_mode: true,
             theme: Theme::System,
             data_retention_days: 30,
+            // Forecasting
+            forecast_method: ForecastMethod::LastDay,
+            auto_select_forecast_method: true,
+            connection_lost_after_minutes: default_connection_lost_after_minutes(),
+            session_expiry_failures: default_session_expiry_failures(),
+            // Mix alerts
+            mix_alerts: MixAlertSettings::default(),
+            // Alert rules
+            alert_rules: Vec::new(),
+            alert_behavior: AlertBehaviorSettings::default(),
+            webhooks: Vec::new(),
+            chat_channels: Vec::new(),
+            email: EmailSettings::default(),
+            quiet_hours: QuietHoursSettings::default(),
+            subscription_alerts: SubscriptionAlertSettings::default(),
+            sounds: SoundSettings::default(),
+            notification_sinks: NotificationSinkSettings::default(),
         }
     }
 }
@@ -130,10 +267,71 @@ impl AppConfig {
                 config::ConfigError::Message("Data retention must be at least 1 day".to_string())
             ));
         }
+
+        if self.session_expiry_failures == 0 {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Session expiry needs at least 1 failed request".to_string())
+            ));
+        }
+
+        if self.mix_alerts.baseline_days == 0 {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Mix alert baseline must be at least 1 day".to_string())
+            ));
+        }
+
+        if !(self.mix_alerts.share_shift_threshold_percent > 0.0 && self.mix_alerts.share_shift_threshold_percent <= 100.0) {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Mix shift threshold must be between 0 and 100 percent".to_string())
+            ));
+        }
+
+        self.augment_endpoints.validate()?;
+        self.http_retry.validate()?;
+        self.network.validate()?;
+        alert_rules::validate_rules(&self.alert_rules)?;
+        webhook::validate_webhooks(&self.webhooks)?;
+        chat_channels::validate_chat_channels(&self.chat_channels)?;
+        self.email.validate()?;
+        self.quiet_hours.validate()?;
+        self.sounds.validate()?;
+        self.notification_sinks.validate(&self.webhooks, &self.chat_channels)?;
+        self.validate_rule_channels(&self.alert_rules)?;
+
+        if !(0.0..=100.0).contains(&self.alert_behavior.hysteresis_percent)
+            || !(0.0..=100.0).contains(&self.alert_behavior.escalation_step_percent)
+        {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Alert hysteresis and escalation margins must be between 0 and 100 percent".to_string())
+            ));
+        }
         
         Ok(())
     }
     
+    /// Every webhook and chat channel used by `rules` must point at a configured one
+    pub fn validate_rule_channels(&self, rules: &[AlertRule]) -> AppResult<()> {
+        for rule in rules {
+            for channel in &rule.channels {
+                let unknown = match channel {
+                    alert_rules::NotificationChannel::Webhook { id } if !self.webhooks.iter().any(|w| &w.id == id) => {
+                        Some(format!("webhook '{}'", id))
+                    }
+                    alert_rules::NotificationChannel::Chat { id } if !self.chat_channels.iter().any(|c| &c.id == id) => {
+                        Some(format!("chat channel '{}'", id))
+                    }
+                    _ => None,
+                };
+                if let Some(unknown) = unknown {
+                    return Err(AppError::Config(
+                        config::ConfigError::Message(format!("Alert rule '{}' uses unknown {}", rule.id, unknown))
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+    
     fn config_file_path() -> AppResult<PathBuf> {
         let config_dir = dirs::config_dir()
             .ok_or_else(|| AppError::Config(
@@ -145,6 +343,15 @@ impl AppConfig {
 
 
 
+    /// Alert rules to evaluate: the user's own, or defaults that follow the balance thresholds
+    pub fn effective_alert_rules(&self) -> Vec<AlertRule> {
+        if self.alert_rules.is_empty() {
+            alert_rules::default_rules(self.low_balance_threshold, self.critical_balance_threshold)
+        } else {
+            self.alert_rules.clone()
+        }
+    }
+
     /// Check if legacy Orb token is configured
     pub fn is_token_configured(&self) -> bool {
         self.orb_token.is_some() && !self.orb_token.as_ref().unwrap().is_empty()
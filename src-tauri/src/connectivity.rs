@@ -0,0 +1,220 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::{self, AppError};
+
+/// Why the provider couldn't be reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Connection refused, reset or otherwise failed
+    Network,
+    Dns,
+    Tls,
+    Timeout,
+    /// The server answered with a 5xx status
+    Server,
+}
+
+impl FailureKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "network error",
+            FailureKind::Dns => "DNS lookup failed",
+            FailureKind::Tls => "TLS handshake failed",
+            FailureKind::Timeout => "request timed out",
+            FailureKind::Server => "server error",
+        }
+    }
+}
+
+/// Connectivity failure behind `error`, or `None` for errors that say nothing about
+/// connectivity (auth, parsing, ...)
+pub fn classify(error: &AppError) -> Option<FailureKind> {
+    match error {
+        AppError::Timeout => Some(FailureKind::Timeout),
+        AppError::Server { .. } => Some(FailureKind::Server),
+        AppError::Network(message) => Some(classify_connect_error(message)),
+        AppError::Http(e) => {
+            if e.is_timeout() {
+                Some(FailureKind::Timeout)
+            } else if e.status().is_some_and(|status| status.is_server_error()) {
+                Some(FailureKind::Server)
+            } else if e.is_connect() || e.is_request() {
+                Some(classify_connect_error(&error::error_chain(e)))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// reqwest reports DNS and TLS problems as connect errors; the cause is only in the message
+fn classify_connect_error(message: &str) -> FailureKind {
+    let message = message.to_lowercase();
+    if ["dns", "lookup address", "name or service not known", "nodename nor servname", "no such host"]
+        .iter()
+        .any(|needle| message.contains(needle))
+    {
+        FailureKind::Dns
+    } else if ["certificate", "tls", "ssl", "handshake"].iter().any(|needle| message.contains(needle)) {
+        FailureKind::Tls
+    } else {
+        FailureKind::Network
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectivityStatus {
+    Online,
+    /// Requests are failing, but not for long enough to call it an outage
+    Unstable { since: DateTime<Utc>, kind: FailureKind, failures: u32 },
+    Offline { since: DateTime<Utc>, kind: FailureKind, failures: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectivityChange {
+    Unchanged,
+    /// The outage just lasted long enough to alert about
+    Lost { since: DateTime<Utc>, kind: FailureKind },
+    /// Requests work again after an alerted outage
+    Restored { outage: Duration },
+}
+
+struct Outage {
+    since: DateTime<Utc>,
+    kind: FailureKind,
+    failures: u32,
+    alerted: bool,
+}
+
+pub struct ConnectivityTracker {
+    outage: Option<Outage>,
+    lost_after: Duration,
+}
+
+impl ConnectivityTracker {
+    pub fn new(lost_after_minutes: u32) -> Self {
+        Self {
+            outage: None,
+            lost_after: Duration::minutes(lost_after_minutes as i64),
+        }
+    }
+
+    pub fn set_lost_after(&mut self, minutes: u32) {
+        self.lost_after = Duration::minutes(minutes as i64);
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        match &self.outage {
+            None => ConnectivityStatus::Online,
+            Some(outage) if outage.alerted => ConnectivityStatus::Offline {
+                since: outage.since,
+                kind: outage.kind,
+                failures: outage.failures,
+            },
+            Some(outage) => ConnectivityStatus::Unstable {
+                since: outage.since,
+                kind: outage.kind,
+                failures: outage.failures,
+            },
+        }
+    }
+
+    pub fn record_failure(&mut self, kind: FailureKind, now: DateTime<Utc>) -> ConnectivityChange {
+        let lost_after = self.lost_after;
+        let outage = self.outage.get_or_insert(Outage {
+            since: now,
+            kind,
+            failures: 0,
+            alerted: false,
+        });
+        outage.kind = kind;
+        outage.failures += 1;
+
+        if !outage.alerted && now - outage.since >= lost_after {
+            outage.alerted = true;
+            return ConnectivityChange::Lost { since: outage.since, kind };
+        }
+        ConnectivityChange::Unchanged
+    }
+
+    pub fn record_success(&mut self, now: DateTime<Utc>) -> ConnectivityChange {
+        match self.outage.take() {
+            Some(outage) if outage.alerted => ConnectivityChange::Restored { outage: now - outage.since },
+            _ => ConnectivityChange::Unchanged,
+        }
+    }
+}
+
+/// Outage length for humans, e.g. "45 min" or "2 h 5 min"
+pub fn describe_outage(outage: Duration) -> String {
+    let minutes = outage.num_minutes().max(1);
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else if minutes % 60 == 0 {
+        format!("{} h", minutes / 60)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_connect_messages() {
+        assert_eq!(
+            classify_connect_error("error sending request: dns error: failed to lookup address information"),
+            FailureKind::Dns
+        );
+        assert_eq!(
+            classify_connect_error("error trying to connect: invalid peer certificate: UnknownIssuer"),
+            FailureKind::Tls
+        );
+        assert_eq!(classify_connect_error("tcp connect error: Connection refused"), FailureKind::Network);
+        assert_eq!(classify(&AppError::Unauthorized("401".to_string())), None);
+        assert_eq!(classify(&AppError::RateLimit { retry_after_secs: Some(30) }), None);
+        assert_eq!(classify(&AppError::Server { status: 502, message: String::new(), retry_after_secs: None }), Some(FailureKind::Server));
+    }
+
+    #[test]
+    fn test_lost_and_restored_once() {
+        let mut tracker = ConnectivityTracker::new(5);
+        let start = Utc::now();
+
+        assert_eq!(tracker.record_failure(FailureKind::Network, start), ConnectivityChange::Unchanged);
+        assert!(matches!(tracker.status(), ConnectivityStatus::Unstable { failures: 1, .. }));
+
+        let lost = tracker.record_failure(FailureKind::Server, start + Duration::minutes(5));
+        assert_eq!(lost, ConnectivityChange::Lost { since: start, kind: FailureKind::Server });
+        assert_eq!(
+            tracker.record_failure(FailureKind::Server, start + Duration::minutes(6)),
+            ConnectivityChange::Unchanged
+        );
+        assert!(matches!(tracker.status(), ConnectivityStatus::Offline { failures: 3, .. }));
+
+        let restored = tracker.record_success(start + Duration::minutes(65));
+        assert_eq!(restored, ConnectivityChange::Restored { outage: Duration::minutes(65) });
+        assert!(matches!(tracker.status(), ConnectivityStatus::Online));
+    }
+
+    #[test]
+    fn test_short_blip_is_silent() {
+        let mut tracker = ConnectivityTracker::new(5);
+        let start = Utc::now();
+
+        tracker.record_failure(FailureKind::Timeout, start);
+        assert_eq!(tracker.record_success(start + Duration::minutes(1)), ConnectivityChange::Unchanged);
+    }
+
+    #[test]
+    fn test_describe_outage() {
+        assert_eq!(describe_outage(Duration::seconds(20)), "1 min");
+        assert_eq!(describe_outage(Duration::minutes(45)), "45 min");
+        assert_eq!(describe_outage(Duration::minutes(120)), "2 h");
+        assert_eq!(describe_outage(Duration::minutes(125)), "2 h 5 min");
+    }
+}
@@ -0,0 +1,255 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::augment_client::{ConsumptionDataPoint, CreditConsumptionResponse, DateRange};
+use crate::error::{AppError, AppResult};
+
+/// Longest range sent in a single /api/credit-consumption request; longer queries are split
+pub const MAX_CHUNK_DAYS: i64 = 90;
+
+/// Dimension the consumption endpoint groups credits by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GroupBy {
+    #[default]
+    None,
+    ModelName,
+    ActivityType,
+}
+
+impl GroupBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupBy::None => "NONE",
+            GroupBy::ModelName => "MODEL_NAME",
+            GroupBy::ActivityType => "ACTIVITY_TYPE",
+        }
+    }
+}
+
+/// Bucket size of the returned data points; `Total` returns one point per group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+    Total,
+}
+
+impl Granularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Day => "DAY",
+            Granularity::Week => "WEEK",
+            Granularity::Month => "MONTH",
+            Granularity::Total => "TOTAL",
+        }
+    }
+
+    /// Start of the bucket containing `at` (UTC, weeks start on Monday)
+    fn bucket_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let date = at.date_naive();
+        let date = match self {
+            Granularity::Day | Granularity::Total => date,
+            Granularity::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+        };
+        Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+    }
+}
+
+/// Credit consumption between two instants, grouped and bucketed as the endpoint allows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsumptionQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(default)]
+    pub group_by: GroupBy,
+    #[serde(default)]
+    pub granularity: Granularity,
+}
+
+impl ConsumptionQuery {
+    /// From UTC midnight `days` ago up to now, so today's consumption is included
+    pub fn last_days(days: u32, group_by: GroupBy, granularity: Granularity) -> Self {
+        let end = Utc::now();
+        Self {
+            start: Granularity::Day.bucket_start(end - Duration::days(days as i64)),
+            end,
+            group_by,
+            granularity,
+        }
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        if self.start >= self.end {
+            return Err(AppError::InvalidInput(format!(
+                "Consumption query must start before it ends ({} - {})",
+                self.start, self.end
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sub-ranges of at most MAX_CHUNK_DAYS each. Boundaries fall on bucket starts, so a bucket
+    /// is never split between two requests.
+    pub fn chunks(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut chunks = Vec::new();
+        let mut chunk_start = self.start;
+
+        while chunk_start < self.end {
+            let limit = chunk_start + Duration::days(MAX_CHUNK_DAYS);
+            let chunk_end = if limit >= self.end {
+                self.end
+            } else {
+                let aligned = self.granularity.bucket_start(limit);
+                if aligned > chunk_start { aligned } else { limit }
+            };
+            chunks.push((chunk_start, chunk_end));
+            chunk_start = chunk_end;
+        }
+        chunks
+    }
+
+    pub fn url(&self, base_url: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+        format!(
+            "{}/api/credit-consumption?groupBy={}&granularity={}&startDateIso={}&endDateIso={}",
+            base_url,
+            self.group_by.as_str(),
+            self.granularity.as_str(),
+            urlencoding::encode(&format_iso(start)),
+            urlencoding::encode(&format_iso(end))
+        )
+    }
+
+    /// Combine chunk responses into one. With `Total` granularity every chunk reports its own
+    /// total, so points of the same group are summed and given the query's full range.
+    pub fn merge(&self, responses: Vec<CreditConsumptionResponse>) -> CreditConsumptionResponse {
+        let mut merged: BTreeMap<(String, String), ConsumptionDataPoint> = BTreeMap::new();
+
+        for point in responses.into_iter().flat_map(|response| response.data_points) {
+            let bucket = match self.granularity {
+                Granularity::Total => String::new(),
+                _ => point.date_range.start_date_iso.clone(),
+            };
+            let key = (bucket, point.group_key.clone().unwrap_or_default());
+
+            match merged.get_mut(&key) {
+                Some(existing) => {
+                    let credits = parse_credits(&existing.credits_consumed) + parse_credits(&point.credits_consumed);
+                    existing.credits_consumed = Some(credits.to_string());
+                    if point.date_range.end_date_iso > existing.date_range.end_date_iso {
+                        existing.date_range.end_date_iso = point.date_range.end_date_iso;
+                    }
+                }
+                None => {
+                    merged.insert(key, point);
+                }
+            }
+        }
+
+        let mut data_points: Vec<ConsumptionDataPoint> = merged.into_values().collect();
+        if self.granularity == Granularity::Total {
+            for point in &mut data_points {
+                point.date_range = DateRange {
+                    start_date_iso: format_iso(self.start),
+                    end_date_iso: format_iso(self.end),
+                };
+            }
+        }
+        CreditConsumptionResponse { data_points }
+    }
+}
+
+fn format_iso(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn parse_credits(credits: &Option<String>) -> i64 {
+    credits.as_ref().and_then(|s| s.parse::<i64>().ok()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc)
+    }
+
+    fn point(start: &str, group: Option<&str>, credits: &str) -> ConsumptionDataPoint {
+        ConsumptionDataPoint {
+            date_range: DateRange { start_date_iso: start.to_string(), end_date_iso: start.to_string() },
+            credits_consumed: Some(credits.to_string()),
+            group_key: group.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_last_days_includes_today() {
+        let query = ConsumptionQuery::last_days(7, GroupBy::None, Granularity::Day);
+        assert!(Utc::now() - query.end < Duration::minutes(1));
+        assert_eq!(query.start.time(), NaiveTime::MIN);
+        assert!(query.validate().is_ok());
+    }
+
+    #[test]
+    fn test_chunks_are_bounded_and_aligned() {
+        let query = ConsumptionQuery {
+            start: at("2025-01-01T00:00:00Z"),
+            end: at("2025-12-31T15:30:00Z"),
+            group_by: GroupBy::ModelName,
+            granularity: Granularity::Month,
+        };
+        let chunks = query.chunks();
+
+        assert_eq!(chunks.first().unwrap().0, query.start);
+        assert_eq!(chunks.last().unwrap().1, query.end);
+        for window in chunks.windows(2) {
+            assert_eq!(window[0].1, window[1].0);
+            assert_eq!(window[0].1.day(), 1);
+        }
+        assert!(chunks.iter().all(|(start, end)| *end - *start <= Duration::days(MAX_CHUNK_DAYS)));
+
+        let short = ConsumptionQuery { end: at("2025-01-05T00:00:00Z"), ..query };
+        assert_eq!(short.chunks().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_sums_totals_across_chunks() {
+        let query = ConsumptionQuery {
+            start: at("2025-01-01T00:00:00Z"),
+            end: at("2025-06-01T00:00:00Z"),
+            group_by: GroupBy::ActivityType,
+            granularity: Granularity::Total,
+        };
+        let merged = query.merge(vec![
+            CreditConsumptionResponse { data_points: vec![point("2025-01-01", Some("CHAT"), "10"), point("2025-01-01", Some("AGENT"), "5")] },
+            CreditConsumptionResponse { data_points: vec![point("2025-04-01", Some("CHAT"), "7")] },
+        ]);
+
+        assert_eq!(merged.data_points.len(), 2);
+        let chat = merged.data_points.iter().find(|p| p.group_key.as_deref() == Some("CHAT")).unwrap();
+        assert_eq!(chat.credits_consumed.as_deref(), Some("17"));
+        assert_eq!(chat.date_range.end_date_iso, "2025-06-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_merge_keeps_buckets_ordered() {
+        let query = ConsumptionQuery {
+            start: at("2025-01-01T00:00:00Z"),
+            end: at("2025-01-03T00:00:00Z"),
+            group_by: GroupBy::None,
+            granularity: Granularity::Day,
+        };
+        let merged = query.merge(vec![
+            CreditConsumptionResponse { data_points: vec![point("2025-01-02T00:00:00Z", None, "3")] },
+            CreditConsumptionResponse { data_points: vec![point("2025-01-01T00:00:00Z", None, "4")] },
+        ]);
+
+        let starts: Vec<&str> = merged.data_points.iter().map(|p| p.date_range.start_date_iso.as_str()).collect();
+        assert_eq!(starts, vec!["2025-01-01T00:00:00Z", "2025-01-02T00:00:00Z"]);
+    }
+}
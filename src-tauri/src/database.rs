@@ -2,6 +2,8 @@ use sqlx::{sqlite::SqlitePool, Row};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::alert_state::{self, AlertState, AlertStatus};
+use crate::analytics::AlertLevel;
 use crate::error::{AppError, AppResult};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +12,8 @@ pub struct BalanceRecord {
     pub amount: u32,
     pub timestamp: DateTime<Utc>,
     pub source: String,
+    #[serde(default)]
+    pub synthetic: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +24,38 @@ pub struct UsageRecord {
     pub usage_amount: u32,
     pub duration_minutes: u32,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub synthetic: bool,
+}
+
+/// A reconstructed balance point produced by the history backfill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticBalancePoint {
+    pub timestamp: DateTime<Utc>,
+    pub amount: u32,
+    pub usage_since_previous: Option<u32>,
+}
+
+/// Server-side daily consumption as reported by the Augment API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyConsumptionRecord {
+    pub date: String,
+    pub credits: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A fired or escalated alert, kept for the dashboard inbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertHistoryEntry {
+    pub id: Uuid,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub level: AlertLevel,
+    pub message: String,
+    pub balance: Option<u32>,
+    pub timestamp: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub snoozed_until: Option<DateTime<Utc>>,
 }
 
 pub struct Database {
@@ -84,7 +120,95 @@ impl Database {
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_timestamp ON usage_records(timestamp)")
             .execute(&self.pool)
             .await?;
-        
+
+        // Mark reconstructed (backfilled) records so they can be told apart from live samples
+        self.add_column_if_missing("balance_records", "synthetic", "INTEGER NOT NULL DEFAULT 0").await?;
+        self.add_column_if_missing("usage_records", "synthetic", "INTEGER NOT NULL DEFAULT 0").await?;
+
+        // Create daily_consumption table (server-reported usage per UTC day)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS daily_consumption (
+                date TEXT PRIMARY KEY,
+                credits INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Create alert_states table (one row per alert rule, survives restarts)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_states (
+                rule_id TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                notified_value REAL,
+                last_value REAL,
+                fired_at TEXT,
+                acknowledged_at TEXT,
+                resolved_at TEXT,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Create alert_history table (every fired alert, for the inbox)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_history (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                level TEXT NOT NULL,
+                message TEXT NOT NULL,
+                balance INTEGER,
+                timestamp TEXT NOT NULL,
+                acknowledged_at TEXT,
+                snoozed_until TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_alert_history_timestamp ON alert_history(timestamp)")
+            .execute(&self.pool)
+            .await?;
+
+        // Create app_metadata table (small key/value bookkeeping, e.g. when the last digest went out)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS app_metadata (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> AppResult<()> {
+        let exists: i64 = sqlx::query_scalar(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?", table)
+        )
+        .bind(column)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if exists == 0 {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await?;
+        }
+
         Ok(())
     }
     
@@ -94,6 +218,7 @@ impl Database {
             amount,
             timestamp: Utc::now(),
             source: "scraper".to_string(),
+            synthetic: false,
         };
         
         sqlx::query(
@@ -134,6 +259,7 @@ impl Database {
             usage_amount,
             duration_minutes,
             timestamp: Utc::now(),
+            synthetic: false,
         };
         
         sqlx::query(
@@ -153,7 +279,7 @@ impl Database {
     
     pub async fn get_latest_balance(&self) -> AppResult<Option<BalanceRecord>> {
         let row = sqlx::query(
-            "SELECT id, amount, timestamp, source FROM balance_records ORDER BY timestamp DESC LIMIT 1"
+            "SELECT id, amount, timestamp, source, synthetic FROM balance_records ORDER BY timestamp DESC LIMIT 1"
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -167,6 +293,7 @@ impl Database {
                     .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
                     .with_timezone(&Utc),
                 source: row.get("source"),
+                synthetic: row.get::<i64, _>("synthetic") != 0,
             }))
         } else {
             Ok(None)
@@ -175,7 +302,7 @@ impl Database {
     
     pub async fn get_previous_balance_record(&self) -> AppResult<Option<BalanceRecord>> {
         let row = sqlx::query(
-            "SELECT id, amount, timestamp, source FROM balance_records ORDER BY timestamp DESC LIMIT 1 OFFSET 1"
+            "SELECT id, amount, timestamp, source, synthetic FROM balance_records ORDER BY timestamp DESC LIMIT 1 OFFSET 1"
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -189,6 +316,7 @@ impl Database {
                     .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
                     .with_timezone(&Utc),
                 source: row.get("source"),
+                synthetic: row.get::<i64, _>("synthetic") != 0,
             }))
         } else {
             Ok(None)
@@ -199,7 +327,7 @@ impl Database {
         let since = Utc::now() - chrono::Duration::hours(hours as i64);
         
         let rows = sqlx::query(
-            "SELECT id, amount, timestamp, source FROM balance_records WHERE timestamp >= ? ORDER BY timestamp ASC"
+            "SELECT id, amount, timestamp, source, synthetic FROM balance_records WHERE timestamp >= ? ORDER BY timestamp ASC"
         )
         .bind(since.to_rfc3339())
         .fetch_all(&self.pool)
@@ -215,6 +343,7 @@ impl Database {
                     .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
                     .with_timezone(&Utc),
                 source: row.get("source"),
+                synthetic: row.get::<i64, _>("synthetic") != 0,
             });
         }
         
@@ -225,7 +354,7 @@ impl Database {
         let since = Utc::now() - chrono::Duration::hours(hours as i64);
         
         let rows = sqlx::query(
-            "SELECT id, start_balance, end_balance, usage_amount, duration_minutes, timestamp FROM usage_records WHERE timestamp >= ? ORDER BY timestamp ASC"
+            "SELECT id, start_balance, end_balance, usage_amount, duration_minutes, timestamp, synthetic FROM usage_records WHERE timestamp >= ? ORDER BY timestamp ASC"
         )
         .bind(since.to_rfc3339())
         .fetch_all(&self.pool)
@@ -243,12 +372,335 @@ impl Database {
                 timestamp: DateTime::parse_from_rfc3339(&row.get::<String, _>("timestamp"))
                     .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
                     .with_timezone(&Utc),
+                synthetic: row.get::<i64, _>("synthetic") != 0,
             });
         }
         
         Ok(records)
     }
     
+    /// Timestamp of the oldest balance sample that was actually observed (not backfilled)
+    pub async fn get_earliest_live_balance_timestamp(&self) -> AppResult<Option<DateTime<Utc>>> {
+        let timestamp: Option<String> = sqlx::query_scalar(
+            "SELECT MIN(timestamp) FROM balance_records WHERE synthetic = 0"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        match timestamp {
+            Some(timestamp) => Ok(Some(
+                DateTime::parse_from_rfc3339(&timestamp)
+                    .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
+                    .with_timezone(&Utc),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn delete_synthetic_records(&self) -> AppResult<()> {
+        sqlx::query("DELETE FROM balance_records WHERE synthetic = 1")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM usage_records WHERE synthetic = 1")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store a reconstructed balance history in one transaction.
+    /// Every point that carries `usage_since_previous` also gets a synthetic usage record.
+    pub async fn insert_synthetic_history(&self, points: &[SyntheticBalancePoint]) -> AppResult<usize> {
+        let mut tx = self.pool.begin().await?;
+        let mut previous: Option<&SyntheticBalancePoint> = None;
+
+        for point in points {
+            sqlx::query(
+                "INSERT INTO balance_records (id, amount, timestamp, source, synthetic) VALUES (?, ?, ?, ?, 1)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(point.amount as i64)
+            .bind(point.timestamp.to_rfc3339())
+            .bind("backfill")
+            .execute(&mut *tx)
+            .await?;
+
+            if let (Some(prev), Some(usage_amount)) = (previous, point.usage_since_previous) {
+                let duration_minutes = point.timestamp
+                    .signed_duration_since(prev.timestamp)
+                    .num_minutes()
+                    .max(1);
+
+                sqlx::query(
+                    "INSERT INTO usage_records (id, start_balance, end_balance, usage_amount, duration_minutes, timestamp, synthetic) VALUES (?, ?, ?, ?, ?, ?, 1)"
+                )
+                .bind(Uuid::new_v4().to_string())
+                .bind(prev.amount as i64)
+                .bind(point.amount as i64)
+                .bind(usage_amount as i64)
+                .bind(duration_minutes)
+                .bind(point.timestamp.to_rfc3339())
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            previous = Some(point);
+        }
+
+        tx.commit().await?;
+        Ok(points.len())
+    }
+
+    pub async fn upsert_daily_consumption(&self, records: &[DailyConsumptionRecord]) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for record in records {
+            sqlx::query(
+                "INSERT INTO daily_consumption (date, credits, updated_at) VALUES (?, ?, ?) \
+                 ON CONFLICT(date) DO UPDATE SET credits = excluded.credits, updated_at = excluded.updated_at"
+            )
+            .bind(&record.date)
+            .bind(record.credits)
+            .bind(record.updated_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_daily_consumption(&self, days: u32) -> AppResult<Vec<DailyConsumptionRecord>> {
+        let since = (Utc::now() - chrono::Duration::days(days as i64)).format("%Y-%m-%d").to_string();
+
+        let rows = sqlx::query(
+            "SELECT date, credits, updated_at FROM daily_consumption WHERE date >= ? ORDER BY date ASC"
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(DailyConsumptionRecord {
+                date: row.get("date"),
+                credits: row.get("credits"),
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                    .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
+                    .with_timezone(&Utc),
+            });
+        }
+
+        Ok(records)
+    }
+
+    pub async fn get_alert_states(&self) -> AppResult<Vec<AlertState>> {
+        let rows = sqlx::query(
+            "SELECT rule_id, status, severity, notified_value, last_value, fired_at, acknowledged_at, resolved_at, updated_at \
+             FROM alert_states ORDER BY rule_id ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut states = Vec::new();
+        for row in rows {
+            let status: String = row.get("status");
+            let severity: String = row.get("severity");
+            states.push(AlertState {
+                rule_id: row.get("rule_id"),
+                status: AlertStatus::parse(&status).unwrap_or_default(),
+                severity: alert_state::parse_level(&severity).unwrap_or(AlertLevel::Info),
+                notified_value: row.get("notified_value"),
+                last_value: row.get("last_value"),
+                fired_at: parse_optional_timestamp(row.get("fired_at"))?,
+                acknowledged_at: parse_optional_timestamp(row.get("acknowledged_at"))?,
+                resolved_at: parse_optional_timestamp(row.get("resolved_at"))?,
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                    .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
+                    .with_timezone(&Utc),
+            });
+        }
+
+        Ok(states)
+    }
+
+    pub async fn save_alert_states(&self, states: &[AlertState]) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for state in states {
+            sqlx::query(
+                "INSERT INTO alert_states (rule_id, status, severity, notified_value, last_value, fired_at, acknowledged_at, resolved_at, updated_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(rule_id) DO UPDATE SET status = excluded.status, severity = excluded.severity, \
+                 notified_value = excluded.notified_value, last_value = excluded.last_value, fired_at = excluded.fired_at, \
+                 acknowledged_at = excluded.acknowledged_at, resolved_at = excluded.resolved_at, updated_at = excluded.updated_at"
+            )
+            .bind(&state.rule_id)
+            .bind(state.status.as_str())
+            .bind(alert_state::level_as_str(state.severity))
+            .bind(state.notified_value)
+            .bind(state.last_value)
+            .bind(state.fired_at.map(|t| t.to_rfc3339()))
+            .bind(state.acknowledged_at.map(|t| t.to_rfc3339()))
+            .bind(state.resolved_at.map(|t| t.to_rfc3339()))
+            .bind(state.updated_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Forget states of rules that no longer exist
+    pub async fn delete_alert_states_except(&self, rule_ids: &[String]) -> AppResult<()> {
+        let existing = self.get_alert_states().await?;
+        for state in existing.iter().filter(|s| !rule_ids.contains(&s.rule_id)) {
+            sqlx::query("DELETE FROM alert_states WHERE rule_id = ?")
+                .bind(&state.rule_id)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert_alert_history(
+        &self,
+        rule_id: &str,
+        rule_name: &str,
+        level: AlertLevel,
+        message: &str,
+        balance: Option<u32>,
+    ) -> AppResult<AlertHistoryEntry> {
+        let entry = AlertHistoryEntry {
+            id: Uuid::new_v4(),
+            rule_id: rule_id.to_string(),
+            rule_name: rule_name.to_string(),
+            level,
+            message: message.to_string(),
+            balance,
+            timestamp: Utc::now(),
+            acknowledged_at: None,
+            snoozed_until: None,
+        };
+
+        sqlx::query(
+            "INSERT INTO alert_history (id, rule_id, rule_name, level, message, balance, timestamp) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(entry.id.to_string())
+        .bind(&entry.rule_id)
+        .bind(&entry.rule_name)
+        .bind(alert_state::level_as_str(entry.level))
+        .bind(&entry.message)
+        .bind(entry.balance.map(|b| b as i64))
+        .bind(entry.timestamp.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Newest first
+    pub async fn get_alert_history(&self, limit: u32, unacknowledged_only: bool) -> AppResult<Vec<AlertHistoryEntry>> {
+        let query = if unacknowledged_only {
+            "SELECT id, rule_id, rule_name, level, message, balance, timestamp, acknowledged_at, snoozed_until \
+             FROM alert_history WHERE acknowledged_at IS NULL ORDER BY timestamp DESC LIMIT ?"
+        } else {
+            "SELECT id, rule_id, rule_name, level, message, balance, timestamp, acknowledged_at, snoozed_until \
+             FROM alert_history ORDER BY timestamp DESC LIMIT ?"
+        };
+
+        let rows = sqlx::query(query)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(alert_history_from_row(&row)?);
+        }
+
+        Ok(entries)
+    }
+
+    pub async fn get_alert_history_entry(&self, id: Uuid) -> AppResult<Option<AlertHistoryEntry>> {
+        let row = sqlx::query(
+            "SELECT id, rule_id, rule_name, level, message, balance, timestamp, acknowledged_at, snoozed_until \
+             FROM alert_history WHERE id = ?"
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| alert_history_from_row(&row)).transpose()
+    }
+
+    /// Returns false when the entry doesn't exist or was already acknowledged
+    pub async fn acknowledge_alert_history(&self, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE alert_history SET acknowledged_at = ? WHERE id = ? AND acknowledged_at IS NULL"
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn acknowledge_all_alert_history(&self) -> AppResult<u64> {
+        let result = sqlx::query("UPDATE alert_history SET acknowledged_at = ? WHERE acknowledged_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn snooze_alert_history(&self, id: Uuid, until: DateTime<Utc>) -> AppResult<bool> {
+        let result = sqlx::query("UPDATE alert_history SET snoozed_until = ? WHERE id = ?")
+            .bind(until.to_rfc3339())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Rules with a snooze that hasn't expired yet
+    pub async fn get_snoozed_rule_ids(&self, now: DateTime<Utc>) -> AppResult<Vec<String>> {
+        let rule_ids = sqlx::query_scalar(
+            "SELECT DISTINCT rule_id FROM alert_history WHERE snoozed_until IS NOT NULL AND snoozed_until > ?"
+        )
+        .bind(now.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rule_ids)
+    }
+
+    pub async fn get_metadata(&self, key: &str) -> AppResult<Option<String>> {
+        let value = sqlx::query_scalar("SELECT value FROM app_metadata WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(value)
+    }
+
+    pub async fn set_metadata(&self, key: &str, value: &str) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO app_metadata (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn cleanup_old_records(&self, retention_days: u32) -> AppResult<()> {
         let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
         
@@ -261,7 +713,40 @@ impl Database {
             .bind(cutoff.to_rfc3339())
             .execute(&self.pool)
             .await?;
+
+        sqlx::query("DELETE FROM alert_history WHERE timestamp < ?")
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
         
         Ok(())
     }
 }
+
+fn parse_optional_timestamp(value: Option<String>) -> AppResult<Option<DateTime<Utc>>> {
+    value
+        .map(|v| {
+            DateTime::parse_from_rfc3339(&v)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))
+        })
+        .transpose()
+}
+
+fn alert_history_from_row(row: &sqlx::sqlite::SqliteRow) -> AppResult<AlertHistoryEntry> {
+    let level: String = row.get("level");
+    Ok(AlertHistoryEntry {
+        id: Uuid::parse_str(&row.get::<String, _>("id"))
+            .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?,
+        rule_id: row.get("rule_id"),
+        rule_name: row.get("rule_name"),
+        level: alert_state::parse_level(&level).unwrap_or(AlertLevel::Info),
+        message: row.get("message"),
+        balance: row.get::<Option<i64>, _>("balance").map(|b| b as u32),
+        timestamp: DateTime::parse_from_rfc3339(&row.get::<String, _>("timestamp"))
+            .map_err(|e| AppError::Database(sqlx::Error::Decode(Box::new(e))))?
+            .with_timezone(&Utc),
+        acknowledged_at: parse_optional_timestamp(row.get("acknowledged_at"))?,
+        snoozed_until: parse_optional_timestamp(row.get("snoozed_until"))?,
+    })
+}
@@ -0,0 +1,439 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Utc, Weekday};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use crate::analytics::AlertLevel;
+use crate::augment_client::{ActivityUsage, ModelUsage};
+use crate::chat_channels::ChatAlert;
+use crate::error::{AppError, AppResult};
+use crate::forecast::ForecastMethod;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Plain connection, for local relays only
+    None,
+    /// Upgrade a plain connection (usually port 587)
+    #[default]
+    StartTls,
+    /// TLS from the first byte (usually port 465)
+    Tls,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestSchedule {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    pub enabled: bool,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub recipients: Vec<String>,
+    /// Alert severities that are emailed individually
+    pub severities: Vec<AlertLevel>,
+    pub digest: DigestSchedule,
+    /// Local hour (0-23) at which the digest goes out
+    pub digest_hour: u32,
+    /// Day of the weekly digest
+    pub digest_weekday: Weekday,
+    pub timeout_seconds: u64,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: None,
+            password: None,
+            from: String::new(),
+            recipients: Vec::new(),
+            severities: vec![AlertLevel::Critical],
+            digest: DigestSchedule::Off,
+            digest_hour: 8,
+            digest_weekday: Weekday::Mon,
+            timeout_seconds: 30,
+        }
+    }
+}
+
+impl EmailSettings {
+    pub fn accepts(&self, level: AlertLevel) -> bool {
+        self.enabled && self.severities.contains(&level)
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let invalid = |reason: String| AppError::Config(
+            config::ConfigError::Message(format!("Email settings: {}", reason))
+        );
+
+        if self.smtp_host.trim().is_empty() {
+            return Err(invalid("SMTP host is required".to_string()));
+        }
+        if self.smtp_port == 0 {
+            return Err(invalid("SMTP port is required".to_string()));
+        }
+        self.from.parse::<Mailbox>()
+            .map_err(|e| invalid(format!("invalid sender '{}': {}", self.from, e)))?;
+        if self.recipients.is_empty() {
+            return Err(invalid("at least one recipient is required".to_string()));
+        }
+        for recipient in &self.recipients {
+            recipient.parse::<Mailbox>()
+                .map_err(|e| invalid(format!("invalid recipient '{}': {}", recipient, e)))?;
+        }
+        if self.digest_hour > 23 {
+            return Err(invalid("digest hour must be between 0 and 23".to_string()));
+        }
+        if self.timeout_seconds == 0 {
+            return Err(invalid("timeout must be at least one second".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a digest should go out at local time `now`, given when the last one was sent
+pub fn digest_due(settings: &EmailSettings, last_sent: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+    if !settings.enabled {
+        return false;
+    }
+
+    let scheduled_today = now.date().and_hms_opt(settings.digest_hour.min(23), 0, 0)
+        .expect("hour below 24 is always valid");
+
+    // Most recent scheduled time at or before `now`
+    let due_at = match settings.digest {
+        DigestSchedule::Off => return false,
+        DigestSchedule::Daily if now < scheduled_today => scheduled_today - Duration::days(1),
+        DigestSchedule::Daily => scheduled_today,
+        DigestSchedule::Weekly => {
+            let mut days_since = (7 + now.weekday().num_days_from_monday() as i64
+                - settings.digest_weekday.num_days_from_monday() as i64) % 7;
+            if days_since == 0 && now < scheduled_today {
+                days_since = 7;
+            }
+            scheduled_today - Duration::days(days_since)
+        }
+    };
+
+    last_sent.map(|sent| sent < due_at).unwrap_or(true)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestForecast {
+    pub method: ForecastMethod,
+    pub daily_rate: f64,
+    pub days_remaining: Option<f64>,
+}
+
+/// Everything that goes into a digest email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestReport {
+    pub schedule: DigestSchedule,
+    pub generated_at: DateTime<Utc>,
+    pub period_days: u32,
+    pub balance: Option<u32>,
+    pub period_usage: i64,
+    pub by_model: Vec<ModelUsage>,
+    pub by_activity: Vec<ActivityUsage>,
+    pub forecast: Option<DigestForecast>,
+    pub portal_url: Option<String>,
+}
+
+/// Subject and plain-text body of a digest
+pub fn render_digest(report: &DigestReport) -> (String, String) {
+    let label = match report.schedule {
+        DigestSchedule::Weekly => "Weekly",
+        _ => "Daily",
+    };
+    let balance = report.balance
+        .map(|b| format!("{} credits", b))
+        .unwrap_or_else(|| "unknown".to_string());
+    let subject = format!("[orb] {} credit digest: {} remaining", label, balance);
+
+    let mut body = String::new();
+    body.push_str(&format!("{} credit digest ({})\n\n", label, report.generated_at.format("%Y-%m-%d %H:%M UTC")));
+    body.push_str(&format!("Balance: {}\n", balance));
+    body.push_str(&format!("Used in the last {} day(s): {} credits\n", report.period_days, report.period_usage));
+
+    body.push_str("\nForecast\n");
+    match &report.forecast {
+        Some(forecast) => {
+            body.push_str(&format!("  Method: {:?}\n", forecast.method));
+            body.push_str(&format!("  Expected usage: {:.0} credits/day\n", forecast.daily_rate));
+            match forecast.days_remaining {
+                Some(days) => body.push_str(&format!("  Credits last about {:.1} more days\n", days)),
+                None => body.push_str("  Credits are not being depleted\n"),
+            }
+        }
+        None => body.push_str("  Not enough history yet\n"),
+    }
+
+    push_breakdown(&mut body, "Usage by model", report.by_model.iter().map(|m| (m.model_name.as_str(), m.credits)));
+    push_breakdown(&mut body, "Usage by activity", report.by_activity.iter().map(|a| (a.activity_type.as_str(), a.credits)));
+
+    if let Some(url) = &report.portal_url {
+        body.push_str(&format!("\nBilling portal: {}\n", url));
+    }
+
+    (subject, body)
+}
+
+fn push_breakdown<'a>(body: &mut String, title: &str, rows: impl Iterator<Item = (&'a str, i64)>) {
+    let mut rows: Vec<(&str, i64)> = rows.collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.1));
+
+    body.push_str(&format!("\n{}\n", title));
+    if rows.is_empty() {
+        body.push_str("  No usage\n");
+    }
+    for (name, credits) in rows {
+        body.push_str(&format!("  {}: {} credits\n", name, credits));
+    }
+}
+
+/// Subject and plain-text body of a single alert
+pub fn render_alert(alert: &ChatAlert) -> (String, String) {
+    let level = match alert.level {
+        AlertLevel::Info => "Info",
+        AlertLevel::Warning => "Warning",
+        AlertLevel::Critical => "Critical",
+    };
+    let subject = format!("[orb] {}: {}", level, alert.title);
+
+    let mut body = format!("{}\n\n{}\n\n", alert.title, alert.message);
+    if let Some(balance) = alert.balance {
+        body.push_str(&format!("Balance: {} credits\n", balance));
+    }
+    if let Some(hours) = alert.hours_to_depletion {
+        body.push_str(&format!("Estimated depletion in: {:.1} hours\n", hours));
+    }
+    if let Some(url) = &alert.portal_url {
+        body.push_str(&format!("Billing portal: {}\n", url));
+    }
+
+    (subject, body)
+}
+
+pub struct EmailSender {
+    settings: EmailSettings,
+}
+
+impl EmailSender {
+    pub fn new(settings: &EmailSettings) -> Self {
+        Self { settings: settings.clone() }
+    }
+
+    fn transport(&self) -> AppResult<AsyncSmtpTransport<Tokio1Executor>> {
+        let host = self.settings.smtp_host.as_str();
+        let tls_parameters = || TlsParameters::new(host.to_string())
+            .map_err(|e| AppError::Notification(format!("Invalid TLS configuration: {}", e)));
+
+        let tls = match self.settings.security {
+            SmtpSecurity::None => Tls::None,
+            SmtpSecurity::StartTls => Tls::Required(tls_parameters()?),
+            SmtpSecurity::Tls => Tls::Wrapper(tls_parameters()?),
+        };
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+            .port(self.settings.smtp_port)
+            .tls(tls)
+            .timeout(Some(std::time::Duration::from_secs(self.settings.timeout_seconds)));
+
+        if let Some(username) = self.settings.username.as_deref().filter(|u| !u.is_empty()) {
+            builder = builder.credentials(Credentials::new(
+                username.to_string(),
+                self.settings.password.clone().unwrap_or_default(),
+            ));
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Send one plain-text message to every configured recipient
+    pub async fn send(&self, subject: &str, body: &str) -> AppResult<()> {
+        let parse = |address: &str| address.parse::<Mailbox>()
+            .map_err(|e| AppError::Notification(format!("Invalid email address '{}': {}", address, e)));
+
+        let mut message = Message::builder()
+            .from(parse(&self.settings.from)?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for recipient in &self.settings.recipients {
+            message = message.to(parse(recipient)?);
+        }
+
+        let message = message.body(body.to_string())
+            .map_err(|e| AppError::Notification(format!("Failed to build email: {}", e)))?;
+
+        self.transport()?
+            .send(message)
+            .await
+            .map_err(|e| AppError::Notification(format!("Failed to send email: {}", e)))?;
+
+        tracing::info!("📧 Sent email to {} recipient(s): {}", self.settings.recipients.len(), subject);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        // 2025-11-03 is a Monday
+        NaiveDate::from_ymd_opt(2025, 11, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn settings(schedule: DigestSchedule) -> EmailSettings {
+        EmailSettings {
+            enabled: true,
+            digest: schedule,
+            digest_hour: 8,
+            digest_weekday: Weekday::Mon,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daily_digest_schedule() {
+        let daily = settings(DigestSchedule::Daily);
+        assert!(digest_due(&daily, None, at(4, 7)));
+        assert!(!digest_due(&daily, Some(at(3, 8)), at(4, 7)));
+        assert!(digest_due(&daily, Some(at(3, 8)), at(4, 8)));
+        assert!(!digest_due(&daily, Some(at(4, 8)), at(4, 20)));
+        assert!(digest_due(&daily, Some(at(4, 8)), at(5, 9)));
+    }
+
+    #[test]
+    fn test_weekly_digest_schedule() {
+        let weekly = settings(DigestSchedule::Weekly);
+        assert!(digest_due(&weekly, Some(at(1, 8)), at(3, 8)));
+        assert!(!digest_due(&weekly, Some(at(3, 8)), at(6, 12)));
+        assert!(!digest_due(&weekly, Some(at(3, 8)), at(10, 7)));
+        // Missed Monday (app was closed): sent on the next run
+        assert!(digest_due(&weekly, Some(at(3, 8)), at(12, 9)));
+        assert!(!digest_due(&settings(DigestSchedule::Off), None, at(3, 9)));
+    }
+
+    #[test]
+    fn test_render_digest() {
+        let report = DigestReport {
+            schedule: DigestSchedule::Weekly,
+            generated_at: Utc::now(),
+            period_days: 7,
+            balance: Some(1200),
+            period_usage: 700,
+            by_model: vec![
+                ModelUsage { model_name: "haiku".to_string(), credits: 100 },
+                ModelUsage { model_name: "sonnet".to_string(), credits: 600 },
+            ],
+            by_activity: Vec::new(),
+            forecast: Some(DigestForecast { method: ForecastMethod::MeanRate, daily_rate: 100.0, days_remaining: Some(12.0) }),
+            portal_url: None,
+        };
+
+        let (subject, body) = render_digest(&report);
+        assert_eq!(subject, "[orb] Weekly credit digest: 1200 credits remaining");
+        assert!(body.find("sonnet: 600").unwrap() < body.find("haiku: 100").unwrap());
+        assert!(body.contains("Credits last about 12.0 more days"));
+        assert!(body.contains("Usage by activity\n  No usage"));
+    }
+
+    /// Minimal SMTP server that accepts one message and returns the envelope and data
+    async fn smtp_stand_in() -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut recipients = Vec::new();
+            let mut data = String::new();
+
+            writer.write_all(b"220 localhost ESMTP stand-in\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let command = line.to_ascii_uppercase();
+                if command.starts_with("EHLO") || command.starts_with("HELO") {
+                    writer.write_all(b"250-localhost\r\n250 8BITMIME\r\n").await.unwrap();
+                } else if command.starts_with("RCPT TO") {
+                    recipients.push(line[8..].trim().to_string());
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                } else if command.starts_with("DATA") {
+                    writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                    while let Some(data_line) = lines.next_line().await.unwrap() {
+                        if data_line == "." {
+                            break;
+                        }
+                        data.push_str(&data_line);
+                        data.push('\n');
+                    }
+                    writer.write_all(b"250 Queued\r\n").await.unwrap();
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                }
+            }
+            (recipients, data)
+        });
+
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn test_send_to_multiple_recipients() {
+        let (port, server) = smtp_stand_in().await;
+        let settings = EmailSettings {
+            enabled: true,
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: port,
+            security: SmtpSecurity::None,
+            from: "orb <monitor@example.com>".to_string(),
+            recipients: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            timeout_seconds: 5,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        let alert = ChatAlert {
+            title: "Critical Balance Alert".to_string(),
+            level: AlertLevel::Critical,
+            message: "Only 80 credits left".to_string(),
+            balance: Some(80),
+            hours_to_depletion: Some(1.5),
+            portal_url: None,
+        };
+        let (subject, body) = render_alert(&alert);
+        EmailSender::new(&settings).send(&subject, &body).await.unwrap();
+
+        let (recipients, data) = server.await.unwrap();
+        assert_eq!(recipients, vec!["<a@example.com>", "<b@example.com>"]);
+        assert!(data.contains("Subject: [orb] Critical: Critical Balance Alert"));
+        assert!(data.contains("Only 80 credits left"));
+    }
+}
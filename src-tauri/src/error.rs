@@ -35,15 +35,33 @@ pub enum AppError {
     #[error("Network timeout")]
     Timeout,
     
-    #[error("Rate limit exceeded")]
-    RateLimit,
+    #[error("Rate limit exceeded{}", retry_after_secs.map(|s| format!(", retry after {}s", s)).unwrap_or_default())]
+    RateLimit { retry_after_secs: Option<u64> },
     
     #[error("Authentication failed")]
     AuthenticationFailed,
 
+    #[error("Unauthorized: {0} - Session may have expired")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Server error {status}: {message}")]
+    Server { status: u16, message: String, retry_after_secs: Option<u64> },
+
+    #[error("Unexpected response: {0}")]
+    BadPayload(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
     #[error("Auth error: {0}")]
     Auth(String),
 
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -59,6 +77,54 @@ impl serde::Serialize for AppError {
 
 pub type AppResult<T> = Result<T, AppError>;
 
+impl AppError {
+    /// The request reached the server but the session was rejected
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(self, AppError::Unauthorized(_) | AppError::AuthenticationFailed)
+    }
+
+    /// Copy of an error produced for an HTTP request, for handing one result to several waiters
+    pub fn duplicate(&self) -> Self {
+        match self {
+            AppError::Timeout => AppError::Timeout,
+            AppError::RateLimit { retry_after_secs } => AppError::RateLimit { retry_after_secs: *retry_after_secs },
+            AppError::AuthenticationFailed => AppError::AuthenticationFailed,
+            AppError::Unauthorized(message) => AppError::Unauthorized(message.clone()),
+            AppError::Forbidden(message) => AppError::Forbidden(message.clone()),
+            AppError::Server { status, message, retry_after_secs } => AppError::Server {
+                status: *status,
+                message: message.clone(),
+                retry_after_secs: *retry_after_secs,
+            },
+            AppError::BadPayload(message) => AppError::BadPayload(message.clone()),
+            AppError::Network(message) => AppError::Network(message.clone()),
+            AppError::Auth(message) => AppError::Auth(message.clone()),
+            other => AppError::Unknown(other.to_string()),
+        }
+    }
+
+    /// Failure to get any response at all: timeouts, connection, DNS and TLS errors
+    pub fn from_transport(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            AppError::Timeout
+        } else {
+            AppError::Network(error_chain(&error))
+        }
+    }
+}
+
+/// `error` followed by all of its sources, which is where reqwest keeps DNS and TLS details
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut text = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        text.push_str(": ");
+        text.push_str(&e.to_string());
+        source = e.source();
+    }
+    text
+}
+
 impl From<AppError> for tauri::Error {
     fn from(err: AppError) -> Self {
         tauri::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))
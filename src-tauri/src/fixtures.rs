@@ -0,0 +1,378 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::{header::{CONTENT_TYPE, ETAG, LAST_MODIFIED, RETRY_AFTER}, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use crate::error::{AppError, AppResult};
+
+/// Environment variable selecting the mode at startup: "record" or "replay"
+pub const MODE_ENV: &str = "ORB_MONITOR_FIXTURES";
+/// Environment variable overriding where fixtures are read from / written to
+pub const DIR_ENV: &str = "ORB_MONITOR_FIXTURE_DIR";
+
+const REDACTED: &str = "REDACTED";
+
+/// Query parameters and JSON keys whose values never end up in a recording
+const SENSITIVE_NAMES: &[&str] = &["token", "cookie", "session", "secret", "password", "authorization", "api_key", "apikey", "email"];
+
+/// Query parameters holding dates relative to now. Requests are matched on how many days ago
+/// they are, so each chunk of a date range keeps its own fixture and recordings still replay
+/// on later days.
+const RELATIVE_DATE_PARAMS: &[&str] = &["startDateIso", "endDateIso"];
+
+/// Session cookie used while replaying when none is configured; replayed requests never
+/// reach a server, so no login is needed
+pub const REPLAY_SESSION: &str = "replay";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureMode {
+    /// Talk to the real APIs
+    Off,
+    /// Talk to the real APIs and save every response
+    Record,
+    /// Serve responses from saved fixtures only
+    Replay,
+}
+
+/// A response as received, or as replayed from a fixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    #[serde(default)]
+    pub retry_after: Option<String>,
+    pub body: String,
+}
+
+impl RecordedResponse {
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// One saved request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    /// Path and query of the request, with secrets redacted
+    pub url: String,
+    pub recorded_at: DateTime<Utc>,
+    pub response: RecordedResponse,
+}
+
+/// Records or replays API traffic depending on how the app was started
+pub struct FixtureStore {
+    mode: FixtureMode,
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new(mode: FixtureMode, dir: PathBuf) -> Self {
+        Self { mode, dir }
+    }
+
+    /// Pass-through store for normal operation
+    pub fn off() -> Self {
+        Self::new(FixtureMode::Off, PathBuf::new())
+    }
+
+    /// Mode and directory from ORB_MONITOR_FIXTURES / ORB_MONITOR_FIXTURE_DIR
+    pub fn from_env() -> Self {
+        let mode = match std::env::var(MODE_ENV).unwrap_or_default().to_lowercase().as_str() {
+            "record" => FixtureMode::Record,
+            "replay" => FixtureMode::Replay,
+            "" | "off" => return Self::off(),
+            other => {
+                tracing::warn!("⚠️ Unknown {} value '{}', fixtures disabled", MODE_ENV, other);
+                return Self::off();
+            }
+        };
+
+        let dir = std::env::var_os(DIR_ENV)
+            .map(PathBuf::from)
+            .or_else(|| dirs::data_dir().map(|dir| dir.join("orb-credit-monitor").join("fixtures")))
+            .unwrap_or_else(|| PathBuf::from("fixtures"));
+        tracing::info!("🎞️ Fixture mode {:?} using {}", mode, dir.display());
+        Self::new(mode, dir)
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// The configured session cookie, or a placeholder when replaying without one
+    pub fn session_cookie(&self, configured: Option<String>) -> Option<String> {
+        configured.or_else(|| (self.mode == FixtureMode::Replay).then(|| REPLAY_SESSION.to_string()))
+    }
+
+    /// Send `request` for `url`, or answer it from a fixture when replaying
+    pub async fn send(&self, url: &str, request: RequestBuilder) -> AppResult<RecordedResponse> {
+        if self.mode == FixtureMode::Replay {
+            return self.replay("GET", url).await;
+        }
+
+        let response = request.send().await.map_err(AppError::from_transport)?;
+        let header = |name: reqwest::header::HeaderName| response.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let status = response.status().as_u16();
+        let content_type = header(CONTENT_TYPE);
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let retry_after = header(RETRY_AFTER);
+        let body = response.text().await.map_err(AppError::from_transport)?;
+        let recorded = RecordedResponse { status, content_type, etag, last_modified, retry_after, body };
+
+        if self.mode == FixtureMode::Record {
+            if let Err(e) = self.record("GET", url, &recorded).await {
+                tracing::warn!("⚠️ Failed to record fixture for {}: {}", sanitize_url(url), e);
+            }
+        }
+        Ok(recorded)
+    }
+
+    async fn record(&self, method: &str, url: &str, response: &RecordedResponse) -> AppResult<()> {
+        let fixture = Fixture {
+            method: method.to_string(),
+            url: sanitize_url(url),
+            recorded_at: Utc::now(),
+            response: RecordedResponse {
+                body: sanitize_body(&response.body),
+                ..response.clone()
+            },
+        };
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.fixture_path(method, url);
+        tokio::fs::write(&path, serde_json::to_string_pretty(&fixture)?).await?;
+        tracing::info!("🎞️ Recorded {} {} to {}", method, fixture.url, path.display());
+        Ok(())
+    }
+
+    async fn replay(&self, method: &str, url: &str) -> AppResult<RecordedResponse> {
+        let path = self.fixture_path(method, url);
+        let content = tokio::fs::read_to_string(&path).await.map_err(|e| {
+            AppError::Unknown(format!("No fixture for {} {} ({}): {}", method, sanitize_url(url), path.display(), e))
+        })?;
+        let fixture: Fixture = serde_json::from_str(&content)?;
+        tracing::info!("🎞️ Replaying {} {} from {}", method, fixture.url, path.display());
+        Ok(fixture.response)
+    }
+
+    fn fixture_path(&self, method: &str, url: &str) -> PathBuf {
+        let key = format!("{} {}", method, match_key(url, Utc::now().date_naive()));
+        let digest = hex::encode(Sha256::digest(key.as_bytes()));
+        let slug: String = url::Url::parse(url)
+            .map(|u| u.path().trim_matches('/').replace('/', "_"))
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .take(60)
+            .collect();
+        self.dir.join(format!("{}-{}.json", slug, &digest[..12]))
+    }
+}
+
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_NAMES.iter().any(|sensitive| name.contains(sensitive))
+}
+
+/// Path and query without the origin, with secret query values redacted, so recordings made
+/// against one origin replay against any other
+pub fn sanitize_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => {
+            let mut path = parsed.path().to_string();
+            let query: Vec<String> = parsed.query_pairs()
+                .map(|(name, value)| {
+                    let value = if is_sensitive(&name) { REDACTED.into() } else { value };
+                    format!("{}={}", name, urlencoding::encode(&value))
+                })
+                .collect();
+            if !query.is_empty() {
+                path.push('?');
+                path.push_str(&query.join("&"));
+            }
+            path
+        }
+        Err(_) => REDACTED.to_string(),
+    }
+}
+
+/// What identifies a request when looking up its fixture: sanitized path and query, sorted,
+/// with dates replaced by their distance from `today`
+fn match_key(url: &str, today: NaiveDate) -> String {
+    let Ok(parsed) = url::Url::parse(url) else { return String::new() };
+    let mut query: Vec<String> = parsed.query_pairs()
+        .map(|(name, value)| {
+            let value = if is_sensitive(&name) {
+                REDACTED.to_string()
+            } else if RELATIVE_DATE_PARAMS.contains(&name.as_ref()) {
+                days_ago(&value, today).map_or_else(|| value.to_string(), |days| format!("today-{}d", days))
+            } else {
+                value.to_string()
+            };
+            format!("{}={}", name, value)
+        })
+        .collect();
+    query.sort();
+    format!("{}?{}", parsed.path(), query.join("&"))
+}
+
+fn days_ago(value: &str, today: NaiveDate) -> Option<i64> {
+    let date = DateTime::parse_from_rfc3339(value)
+        .map(|date| date.date_naive())
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+        .ok()?;
+    Some((today - date).num_days())
+}
+
+/// `value` with sensitive query values redacted if it is a URL, keeping its origin
+fn redact_url(value: &str) -> Option<String> {
+    let mut parsed = url::Url::parse(value).ok()?;
+    if !parsed.query_pairs().any(|(name, _)| is_sensitive(&name)) {
+        return None;
+    }
+
+    let pairs: Vec<(String, String)> = parsed.query_pairs()
+        .map(|(name, value)| {
+            let value = if is_sensitive(&name) { REDACTED.to_string() } else { value.to_string() };
+            (name.to_string(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    Some(parsed.to_string())
+}
+
+/// JSON body with sensitive values replaced; non-JSON bodies are kept as they are
+pub fn sanitize_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            serde_json::to_string(&value).unwrap_or_default()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        // e.g. portal links that carry an access token
+        Value::String(text) => {
+            if let Some(redacted) = redact_url(text) {
+                *text = redacted;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fixtures-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_secrets_are_scrubbed() {
+        assert_eq!(
+            sanitize_url("https://portal.withorb.com/api/v1/customers/c1/ledger_summary?pricing_unit_id=p1&token=abc123"),
+            "/api/v1/customers/c1/ledger_summary?pricing_unit_id=p1&token=REDACTED"
+        );
+
+        let body = sanitize_body(r#"{"email":"me@corp.com","planName":"Pro","nested":[{"sessionToken":"xyz","credits":5}],"portalUrl":null}"#);
+        let value: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["email"], "REDACTED");
+        assert_eq!(value["planName"], "Pro");
+        assert_eq!(value["nested"][0]["sessionToken"], "REDACTED");
+        assert_eq!(value["nested"][0]["credits"], 5);
+        assert!(value["portalUrl"].is_null());
+    }
+
+    #[test]
+    fn test_match_key_ignores_origin_and_keeps_relative_date_range() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let chunk = |origin: &str, start: &str, end: &str| format!(
+            "{}/api/credit-consumption?groupBy=NONE&startDateIso={}&endDateIso={}",
+            origin, urlencoding::encode(start), urlencoding::encode(end)
+        );
+
+        // The same relative range recorded on another day and against another origin
+        assert_eq!(
+            match_key(&chunk("https://app.augmentcode.com", "2025-02-01T00:00:00.000Z", "2025-03-01T09:30:00.000Z"), day(1)),
+            match_key(&chunk("http://127.0.0.1:8080", "2025-02-06T00:00:00.000Z", "2025-03-06T14:00:00.000Z"), day(6))
+        );
+        // Chunks of one long range keep their own fixtures
+        assert_ne!(
+            match_key(&chunk("https://app.augmentcode.com", "2025-01-01T00:00:00.000Z", "2025-02-01T00:00:00.000Z"), day(1)),
+            match_key(&chunk("https://app.augmentcode.com", "2025-02-01T00:00:00.000Z", "2025-03-01T00:00:00.000Z"), day(1))
+        );
+        assert_ne!(
+            match_key("https://app.augmentcode.com/api/credit-consumption?groupBy=NONE", day(1)),
+            match_key("https://app.augmentcode.com/api/credit-consumption?groupBy=MODEL_NAME", day(1))
+        );
+    }
+
+    #[test]
+    fn test_tokens_in_url_values_are_scrubbed() {
+        let body = sanitize_body(r#"{"portalUrl":"https://portal.withorb.com/view?token=abc123&lang=en","docs":"https://docs.augmentcode.com/"}"#);
+        let value: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["portalUrl"], "https://portal.withorb.com/view?token=REDACTED&lang=en");
+        assert_eq!(value["docs"], "https://docs.augmentcode.com/");
+    }
+
+    #[test]
+    fn test_replay_needs_no_login() {
+        let player = FixtureStore::new(FixtureMode::Replay, PathBuf::new());
+        assert_eq!(player.session_cookie(None).as_deref(), Some(REPLAY_SESSION));
+        assert_eq!(player.session_cookie(Some("real".to_string())).as_deref(), Some("real"));
+        assert_eq!(FixtureStore::off().session_cookie(None), None);
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = temp_dir("roundtrip");
+        let recorder = FixtureStore::new(FixtureMode::Record, dir.clone());
+        let response = RecordedResponse {
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            retry_after: None,
+            body: r#"{"email":"me@corp.com","isAdmin":false}"#.to_string(),
+        };
+        recorder.record("GET", "https://app.augmentcode.com/api/user", &response).await.unwrap();
+
+        let player = FixtureStore::new(FixtureMode::Replay, dir.clone());
+        let request = reqwest::Client::new().get("http://127.0.0.1:9/api/user");
+        let replayed = player.send("http://127.0.0.1:9/api/user", request).await.unwrap();
+        assert_eq!(replayed.status(), StatusCode::OK);
+        assert_eq!(replayed.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(replayed.body, r#"{"email":"REDACTED","isAdmin":false}"#);
+
+        let missing = player.send("http://127.0.0.1:9/api/credits", reqwest::Client::new().get("http://127.0.0.1:9/api/credits")).await;
+        assert!(missing.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
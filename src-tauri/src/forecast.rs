@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+/// Daily-usage forecasters that can be replayed against stored history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForecastMethod {
+    /// Extrapolate the most recent complete day
+    #[default]
+    LastDay,
+    /// Mean of the whole history
+    MeanRate,
+    /// Mean of the last seven days
+    MovingAverage7d,
+    /// Exponentially weighted mean, recent days weigh more
+    ExponentialSmoothing,
+    /// Least-squares line over the last two weeks, extrapolated forward
+    LinearTrend,
+}
+
+const SMOOTHING_ALPHA: f64 = 0.3;
+const TREND_WINDOW_DAYS: usize = 14;
+
+impl ForecastMethod {
+    pub const ALL: [ForecastMethod; 5] = [
+        ForecastMethod::LastDay,
+        ForecastMethod::MeanRate,
+        ForecastMethod::MovingAverage7d,
+        ForecastMethod::ExponentialSmoothing,
+        ForecastMethod::LinearTrend,
+    ];
+
+    /// Predicted total usage over the next `horizon_days`, given complete daily totals (oldest first)
+    pub fn predict_usage(&self, history: &[f64], horizon_days: usize) -> Option<f64> {
+        if history.is_empty() || horizon_days == 0 {
+            return None;
+        }
+
+        let horizon = horizon_days as f64;
+        let prediction = match self {
+            ForecastMethod::LastDay => history[history.len() - 1] * horizon,
+            ForecastMethod::MeanRate => mean(history) * horizon,
+            ForecastMethod::MovingAverage7d => {
+                mean(&history[history.len().saturating_sub(7)..]) * horizon
+            }
+            ForecastMethod::ExponentialSmoothing => {
+                let level = history[1..].iter()
+                    .fold(history[0], |level, &value| SMOOTHING_ALPHA * value + (1.0 - SMOOTHING_ALPHA) * level);
+                level * horizon
+            }
+            ForecastMethod::LinearTrend => {
+                let window = &history[history.len().saturating_sub(TREND_WINDOW_DAYS)..];
+                let (intercept, slope) = linear_fit(window);
+                let n = window.len() as f64;
+                (1..=horizon_days)
+                    .map(|k| (intercept + slope * (n - 1.0 + k as f64)).max(0.0))
+                    .sum()
+            }
+        };
+
+        Some(prediction.max(0.0))
+    }
+
+    /// Predicted average usage per day over the next `horizon_days`
+    pub fn predict_daily_rate(&self, history: &[f64], horizon_days: usize) -> Option<f64> {
+        self.predict_usage(history, horizon_days)
+            .map(|total| total / horizon_days as f64)
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// Ordinary least squares over (index, value), returns (intercept, slope)
+fn linear_fit(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    if values.len() < 2 {
+        return (mean(values), 0.0);
+    }
+
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = mean(values);
+
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (i, &y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        covariance += dx * (y - mean_y);
+        variance += dx * dx;
+    }
+
+    let slope = if variance > 0.0 { covariance / variance } else { 0.0 };
+    (mean_y - slope * mean_x, slope)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodAccuracy {
+    pub method: ForecastMethod,
+    pub samples: usize,
+    /// Mean absolute error, in credits over the horizon
+    pub mae: f64,
+    /// Root mean squared error, in credits over the horizon
+    pub rmse: f64,
+    /// Mean absolute percentage error; windows with zero actual usage are skipped
+    pub mape: Option<f64>,
+    /// Mean signed error (positive = over-forecasting)
+    pub bias: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub horizon_days: usize,
+    pub history_days: usize,
+    pub methods: Vec<MethodAccuracy>,
+    pub best_method: Option<ForecastMethod>,
+}
+
+/// Replay `series` (complete daily totals, oldest first): at every day with at least
+/// `min_history_days` behind it, each method forecasts the next `horizon_days` and is scored
+/// against what was actually consumed.
+pub fn backtest(series: &[f64], horizon_days: usize, min_history_days: usize) -> BacktestReport {
+    let min_history_days = min_history_days.max(1);
+
+    let methods: Vec<MethodAccuracy> = ForecastMethod::ALL.iter()
+        .filter_map(|method| {
+            let mut errors = Vec::new();
+            let mut percentage_errors = Vec::new();
+
+            let mut cutoff = min_history_days;
+            while horizon_days > 0 && cutoff + horizon_days <= series.len() {
+                let actual: f64 = series[cutoff..cutoff + horizon_days].iter().sum();
+                if let Some(predicted) = method.predict_usage(&series[..cutoff], horizon_days) {
+                    errors.push(predicted - actual);
+                    if actual > 0.0 {
+                        percentage_errors.push(((predicted - actual) / actual).abs() * 100.0);
+                    }
+                }
+                cutoff += 1;
+            }
+
+            if errors.is_empty() {
+                return None;
+            }
+
+            let samples = errors.len() as f64;
+            Some(MethodAccuracy {
+                method: *method,
+                samples: errors.len(),
+                mae: errors.iter().map(|e| e.abs()).sum::<f64>() / samples,
+                rmse: (errors.iter().map(|e| e * e).sum::<f64>() / samples).sqrt(),
+                mape: (!percentage_errors.is_empty()).then(|| mean(&percentage_errors)),
+                bias: errors.iter().sum::<f64>() / samples,
+            })
+        })
+        .collect();
+
+    let best_method = methods.iter()
+        .min_by(|a, b| {
+            a.mae.partial_cmp(&b.mae)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.rmse.partial_cmp(&b.rmse).unwrap_or(std::cmp::Ordering::Equal))
+        })
+        .map(|accuracy| accuracy.method);
+
+    BacktestReport {
+        horizon_days,
+        history_days: series.len(),
+        methods,
+        best_method,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predictions_on_constant_usage() {
+        let history = vec![10.0; 20];
+        for method in ForecastMethod::ALL {
+            let predicted = method.predict_usage(&history, 7).unwrap();
+            assert!((predicted - 70.0).abs() < 1e-9, "{:?} predicted {}", method, predicted);
+        }
+    }
+
+    #[test]
+    fn test_linear_trend_extrapolates_growth() {
+        let history: Vec<f64> = (0..14).map(|i| i as f64 * 10.0).collect();
+        let predicted = ForecastMethod::LinearTrend.predict_usage(&history, 2).unwrap();
+        assert!((predicted - (140.0 + 150.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_backtest_prefers_trend_for_growing_usage() {
+        let series: Vec<f64> = (0..40).map(|i| 5.0 + i as f64 * 3.0).collect();
+        let report = backtest(&series, 7, 14);
+
+        assert_eq!(report.methods.len(), ForecastMethod::ALL.len());
+        assert_eq!(report.best_method, Some(ForecastMethod::LinearTrend));
+
+        let mean_rate = report.methods.iter()
+            .find(|m| m.method == ForecastMethod::MeanRate)
+            .unwrap();
+        assert!(mean_rate.bias < 0.0, "mean rate should under-forecast rising usage");
+    }
+
+    #[test]
+    fn test_backtest_without_enough_history() {
+        let report = backtest(&[1.0, 2.0, 3.0], 7, 7);
+        assert!(report.methods.is_empty());
+        assert_eq!(report.best_method, None);
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Which Augment responses are cached and for how long; a TTL of 0 disables caching for that
+/// endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpCacheSettings {
+    pub enabled: bool,
+    pub subscription_ttl_seconds: u64,
+    pub user_ttl_seconds: u64,
+    pub credits_ttl_seconds: u64,
+    /// Keep cached responses across restarts, so the dashboard has data before the first fetch
+    pub persist_to_disk: bool,
+}
+
+impl Default for HttpCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            subscription_ttl_seconds: 3600,
+            user_ttl_seconds: 3600,
+            credits_ttl_seconds: 0,
+            persist_to_disk: false,
+        }
+    }
+}
+
+/// Augment endpoints whose responses may be cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachedEndpoint {
+    Subscription,
+    User,
+    Credits,
+}
+
+impl HttpCacheSettings {
+    /// TTL for `endpoint`, or `None` if it isn't cached
+    pub fn ttl_for(&self, endpoint: CachedEndpoint) -> Option<Duration> {
+        if !self.enabled {
+            return None;
+        }
+        let seconds = match endpoint {
+            CachedEndpoint::Subscription => self.subscription_ttl_seconds,
+            CachedEndpoint::User => self.user_ttl_seconds,
+            CachedEndpoint::Credits => self.credits_ttl_seconds,
+        };
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+}
+
+/// A response body with the validators needed to revalidate it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the body was last confirmed by the server
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl CachedResponse {
+    pub fn is_fresh(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        (now - self.fetched_at).to_std().map_or(true, |age| age < ttl)
+    }
+
+    pub fn can_revalidate(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// How current a value handed to the frontend is
+#[derive(Debug, Clone, Serialize)]
+pub struct Freshness {
+    pub fetched_at: DateTime<Utc>,
+    pub age_seconds: i64,
+    /// Older than its TTL; a background refresh is on its way
+    pub stale: bool,
+    pub from_cache: bool,
+}
+
+impl Freshness {
+    /// Just fetched from the server
+    pub fn live(now: DateTime<Utc>) -> Self {
+        Self { fetched_at: now, age_seconds: 0, stale: false, from_cache: false }
+    }
+
+    pub fn cached(entry: &CachedResponse, ttl: Duration, now: DateTime<Utc>) -> Self {
+        Self {
+            fetched_at: entry.fetched_at,
+            age_seconds: (now - entry.fetched_at).num_seconds().max(0),
+            stale: !entry.is_fresh(ttl, now),
+            from_cache: true,
+        }
+    }
+}
+
+/// Response cache of one Augment account, keyed by URL
+pub struct HttpCache {
+    settings: HttpCacheSettings,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    file: Option<PathBuf>,
+}
+
+impl HttpCache {
+    /// Cache for the account behind `session_cookie`, loaded from disk if persistence is on
+    pub fn new(settings: &HttpCacheSettings, session_cookie: &str) -> Self {
+        let file = (settings.enabled && settings.persist_to_disk)
+            .then(|| cache_file(session_cookie))
+            .flatten();
+        Self::with_file(settings, file)
+    }
+
+    pub fn with_file(settings: &HttpCacheSettings, file: Option<PathBuf>) -> Self {
+        let entries = file.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            settings: settings.clone(),
+            entries: Mutex::new(entries),
+            file,
+        }
+    }
+
+    pub fn ttl_for(&self, endpoint: CachedEndpoint) -> Option<Duration> {
+        self.settings.ttl_for(endpoint)
+    }
+
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).get(url).cloned()
+    }
+
+    pub async fn store(&self, url: &str, entry: CachedResponse) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(url.to_string(), entry);
+        self.save().await;
+    }
+
+    /// The server confirmed the cached body is still current (304)
+    pub async fn revalidated(&self, url: &str, now: DateTime<Utc>) -> Option<CachedResponse> {
+        let entry = {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = entries.get_mut(url)?;
+            entry.fetched_at = now;
+            entry.clone()
+        };
+        self.save().await;
+        Some(entry)
+    }
+
+    /// Forget everything, including the on-disk copy
+    pub fn purge(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        if let Some(path) = &self.file {
+            if let Err(e) = std::fs::remove_file(path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("⚠️ Failed to remove HTTP cache {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    async fn save(&self) {
+        let Some(path) = &self.file else { return };
+        let content = {
+            let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            match serde_json::to_string(&*entries) {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::warn!("⚠️ Failed to serialize HTTP cache: {}", e);
+                    return;
+                }
+            }
+        };
+
+        if let Some(parent) = path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        if let Err(e) = tokio::fs::write(path, content).await {
+            tracing::warn!("⚠️ Failed to write HTTP cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Per-account cache file; named by a hash so the session cookie never ends up on disk
+fn cache_file(session_cookie: &str) -> Option<PathBuf> {
+    let digest = hex::encode(Sha256::digest(session_cookie.as_bytes()));
+    dirs::cache_dir().map(|dir| {
+        dir.join("orb-credit-monitor")
+            .join(format!("http-cache-{}.json", &digest[..16]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(fetched_at: DateTime<Utc>) -> CachedResponse {
+        CachedResponse {
+            body: "{}".to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched_at,
+        }
+    }
+
+    #[test]
+    fn test_ttl_per_endpoint() {
+        let settings = HttpCacheSettings::default();
+        assert_eq!(settings.ttl_for(CachedEndpoint::Subscription), Some(Duration::from_secs(3600)));
+        assert_eq!(settings.ttl_for(CachedEndpoint::Credits), None);
+
+        let disabled = HttpCacheSettings { enabled: false, ..HttpCacheSettings::default() };
+        assert_eq!(disabled.ttl_for(CachedEndpoint::User), None);
+    }
+
+    #[test]
+    fn test_freshness_reports_staleness() {
+        let now = Utc::now();
+        let ttl = Duration::from_secs(60);
+
+        let fresh = Freshness::cached(&entry(now - chrono::Duration::seconds(30)), ttl, now);
+        assert!(!fresh.stale && fresh.from_cache);
+        assert_eq!(fresh.age_seconds, 30);
+
+        let stale = Freshness::cached(&entry(now - chrono::Duration::seconds(90)), ttl, now);
+        assert!(stale.stale);
+    }
+
+    #[tokio::test]
+    async fn test_persists_and_purges() {
+        let path = std::env::temp_dir().join(format!("http-cache-test-{}.json", std::process::id()));
+        let settings = HttpCacheSettings { persist_to_disk: true, ..HttpCacheSettings::default() };
+
+        let cache = HttpCache::with_file(&settings, Some(path.clone()));
+        let old = Utc::now() - chrono::Duration::hours(2);
+        cache.store("https://example.com/api/user", entry(old)).await;
+
+        let reloaded = HttpCache::with_file(&settings, Some(path.clone()));
+        assert_eq!(reloaded.get("https://example.com/api/user").unwrap().etag.as_deref(), Some("\"v1\""));
+
+        let now = Utc::now();
+        let revalidated = reloaded.revalidated("https://example.com/api/user", now).await.unwrap();
+        assert!(revalidated.is_fresh(Duration::from_secs(60), now));
+
+        reloaded.purge();
+        assert!(reloaded.get("https://example.com/api/user").is_none());
+        assert!(!path.exists());
+    }
+}
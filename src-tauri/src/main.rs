@@ -3,6 +3,7 @@
 
 use tauri::{Manager, Emitter, menu::{Menu, MenuItem}, tray::{TrayIconBuilder, TrayIconEvent, MouseButton, MouseButtonState}, WindowEvent, WebviewUrl, WebviewWindowBuilder};
 use tauri::webview::PageLoadEvent;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -13,25 +14,57 @@ mod database;
 mod scraper;
 mod analytics;
 mod notifications;
+mod notification_sinks;
 mod error;
 mod augment_client;
+mod backfill;
+mod statistics;
+mod forecast;
+mod mix_alerts;
+mod alert_rules;
+mod alert_state;
+mod webhook;
+mod chat_channels;
+mod email;
+mod quiet_hours;
+mod sounds;
+mod session;
+mod connectivity;
+mod retry;
+mod subscription_watch;
+mod consumption_query;
+mod schema_drift;
+mod http_cache;
+mod network;
+mod fixtures;
 
 use config::AppConfig;
 use database::Database;
-use scraper::orbScraper;
+use scraper::ScraperHandle;
 use analytics::AnalyticsEngine;
 use notifications::NotificationManager;
 use error::{AppResult, AppError};
-use augment_client::{AugmentClient, CreditsResponse, SubscriptionResponse, AugmentBalanceInfo};
+use http_cache::Freshness;
+use fixtures::FixtureStore;
+use session::{SessionChange, SessionTracker};
+use connectivity::{ConnectivityChange, ConnectivityTracker};
+use augment_client::{AugmentClient, AugmentClientCache, CreditsResponse, SubscriptionResponse, AugmentBalanceInfo};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<Mutex<AppConfig>>,
     pub database: Arc<Database>,
-    pub scraper: Arc<orbScraper>,
+    pub scraper: Arc<ScraperHandle>,
     pub analytics: Arc<AnalyticsEngine>,
     pub notifications: Arc<Mutex<NotificationManager>>,
     pub window_visible: Arc<Mutex<bool>>,
+    pub last_mix_check: Arc<Mutex<Option<chrono::NaiveDate>>>,
+    pub subscription: Arc<Mutex<Option<SubscriptionResponse>>>,
+    pub session: Arc<Mutex<SessionTracker>>,
+    pub connectivity: Arc<Mutex<ConnectivityTracker>>,
+    pub augment_clients: Arc<Mutex<AugmentClientCache>>,
+    /// Record/replay of API traffic, chosen at startup with ORB_MONITOR_FIXTURES
+    pub fixtures: Arc<FixtureStore>,
 }
 
 #[tauri::command]
@@ -53,19 +86,18 @@ async fn fetch_fresh_balance(
     tracing::info!("🔄 FETCH FRESH BALANCE - Direct HTTP call");
 
     // Build URL from configuration
-    let url = {
+    let (url, network) = {
         let config = state.config.lock().await;
-        config.build_ledger_url()?
+        (config.build_ledger_url()?, config.network.clone())
     };
 
     tracing::info!("🔄 Using dynamic URL: {}", url);
 
-    let client = reqwest::Client::new();
-    let response = client
+    let client = network.client_builder()?.build()?;
+    let request = client
         .get(&url)
-        .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
-        .send()
-        .await?;
+        .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36");
+    let response = state.fixtures.send(&url, request).await?;
 
     if !response.status().is_success() {
         return Err(std::io::Error::new(
@@ -74,7 +106,7 @@ async fn fetch_fresh_balance(
         ).into());
     }
 
-    let json: serde_json::Value = response.json().await?;
+    let json: serde_json::Value = serde_json::from_str(&response.body)?;
     tracing::info!("🔄 FETCH FRESH BALANCE - Response: {:?}", json);
 
     // Extract balance from JSON
@@ -86,7 +118,7 @@ async fn fetch_fresh_balance(
 
                 // Store in database for analytics
                 tracing::info!("💾 Storing fresh balance in database...");
-                if let Err(e) = state.database.insert_balance_record(balance_credits).await {
+                if let Err(e) = ingest_balance(&state, balance_credits, None).await {
                     tracing::error!("❌ Failed to store balance in database: {}", e);
                 }
 
@@ -121,21 +153,300 @@ async fn get_usage_analytics(
     hours: Option<u32>,
 ) -> AppResult<analytics::UsageAnalytics> {
     let hours = hours.unwrap_or(24);
-    let analytics = state.analytics.calculate_usage_analytics(hours).await?;
+    let mut analytics = state.analytics.calculate_usage_analytics(hours).await?;
+
+    let forecast_method = {
+        let config = state.config.lock().await;
+        config.forecast_method
+    };
+    state.analytics.apply_forecast_method(&mut analytics, forecast_method).await?;
+
     Ok(analytics)
 }
 
+/// Replay stored history through every forecasting method and report their accuracy.
+/// With auto-selection enabled the best method becomes the one used for depletion estimates.
+#[tauri::command]
+async fn run_forecast_backtest(
+    state: tauri::State<'_, AppState>,
+    days: Option<u32>,
+    horizon_days: Option<usize>,
+) -> AppResult<forecast::BacktestReport> {
+    let days = days.unwrap_or(90);
+    let horizon_days = horizon_days.unwrap_or(7);
+    tracing::info!("🧪 RUN FORECAST BACKTEST (last {} days, {} day horizon)", days, horizon_days);
+
+    let report = state.analytics.run_forecast_backtest(days, horizon_days).await?;
+    auto_select_forecast_method(&state, &report).await?;
+
+    Ok(report)
+}
+
+async fn auto_select_forecast_method(state: &AppState, report: &forecast::BacktestReport) -> AppResult<()> {
+    let mut config = state.config.lock().await;
+
+    if let Some(best_method) = report.best_method {
+        if config.auto_select_forecast_method && config.forecast_method != best_method {
+            tracing::info!("🧪 Switching forecast method {:?} -> {:?}", config.forecast_method, best_method);
+            config.forecast_method = best_method;
+            config.save().await?;
+        }
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 async fn update_config(
     state: tauri::State<'_, AppState>,
     new_config: config::AppConfig,
 ) -> AppResult<()> {
+    new_config.validate()?;
+
+    let network_changed = state.config.lock().await.network != new_config.network;
+    if network_changed {
+        state.scraper.set_network(&new_config.network).await?;
+    }
+
+    state.notifications.lock().await.configure(&new_config);
+    state.session.lock().await.set_failure_threshold(new_config.session_expiry_failures);
+    state.connectivity.lock().await.set_lost_after(new_config.connection_lost_after_minutes);
+
     let mut config = state.config.lock().await;
     *config = new_config;
     config.save().await?;
     Ok(())
 }
 
+/// Play the alert sound for `level` with the given (possibly unsaved) settings
+#[tauri::command]
+async fn test_alert_sound(level: analytics::AlertLevel, settings: sounds::SoundSettings) -> AppResult<bool> {
+    tracing::info!("🔊 TEST ALERT SOUND: {:?}", level);
+    settings.validate()?;
+
+    let playback = sounds::SoundPlayer::new().play(level, &settings).await?;
+    Ok(playback == sounds::Playback::Started)
+}
+
+#[derive(serde::Serialize)]
+struct QuietHoursStatus {
+    active_until: Option<chrono::DateTime<chrono::Utc>>,
+    deferred: Vec<quiet_hours::DeferredNotification>,
+}
+
+/// Whether quiet hours are in effect and what is waiting for the summary
+#[tauri::command]
+async fn get_quiet_hours_status(state: tauri::State<'_, AppState>) -> AppResult<QuietHoursStatus> {
+    let settings = state.config.lock().await.quiet_hours.clone();
+    let notifications = state.notifications.lock().await;
+
+    Ok(QuietHoursStatus {
+        active_until: settings.active_until(chrono::Utc::now()),
+        deferred: notifications.deferred_notifications().to_vec(),
+    })
+}
+
+/// Recent per-sink delivery results, newest first
+#[tauri::command]
+async fn get_notification_deliveries(state: tauri::State<'_, AppState>) -> AppResult<Vec<notification_sinks::DeliveryOutcome>> {
+    Ok(state.notifications.lock().await.delivery_outcomes())
+}
+
+/// Whether the stored session still works, is failing, or has expired
+#[tauri::command]
+async fn get_session_status(state: tauri::State<'_, AppState>) -> AppResult<session::SessionStatus> {
+    Ok(state.session.lock().await.status())
+}
+
+/// Whether the provider is reachable, and since when it isn't
+#[tauri::command]
+async fn get_connectivity_status(state: tauri::State<'_, AppState>) -> AppResult<connectivity::ConnectivityStatus> {
+    Ok(state.connectivity.lock().await.status())
+}
+
+/// Alert rules currently in effect (defaults when none are configured)
+#[tauri::command]
+async fn get_alert_rules(state: tauri::State<'_, AppState>) -> AppResult<Vec<alert_rules::AlertRule>> {
+    let config = state.config.lock().await;
+    Ok(config.effective_alert_rules())
+}
+
+/// Validate and store user-defined alert rules; an empty list restores the defaults
+#[tauri::command]
+async fn save_alert_rules(
+    state: tauri::State<'_, AppState>,
+    rules: Vec<alert_rules::AlertRule>,
+) -> AppResult<()> {
+    tracing::info!("🔔 SAVE ALERT RULES ({} rules)", rules.len());
+    alert_rules::validate_rules(&rules)?;
+
+    let mut config = state.config.lock().await;
+    config.validate_rule_channels(&rules)?;
+    config.alert_rules = rules;
+    config.save().await?;
+
+    let rule_ids: Vec<String> = config.effective_alert_rules().into_iter().map(|rule| rule.id).collect();
+    state.database.delete_alert_states_except(&rule_ids).await?;
+    Ok(())
+}
+
+/// Send a sample alert to a webhook, saved or not yet saved
+#[tauri::command]
+async fn test_webhook(
+    state: tauri::State<'_, AppState>,
+    webhook: webhook::WebhookConfig,
+) -> AppResult<webhook::WebhookDelivery> {
+    tracing::info!("🧪 TEST WEBHOOK: {}", webhook.name);
+    webhook.validate().map_err(|reason| AppError::InvalidInput(format!("Webhook '{}': {}", webhook.id, reason)))?;
+
+    let notifications = state.notifications.lock().await;
+    Ok(notifications.test_webhook(&webhook).await)
+}
+
+/// Send a sample alert to a Slack, Discord or Teams channel
+#[tauri::command]
+async fn test_chat_channel(
+    state: tauri::State<'_, AppState>,
+    channel: chat_channels::ChatChannelConfig,
+) -> AppResult<webhook::WebhookDelivery> {
+    tracing::info!("🧪 TEST {} CHANNEL: {}", channel.platform.label(), channel.name);
+    chat_channels::validate_chat_channels(std::slice::from_ref(&channel))?;
+
+    let portal_url = state.subscription.lock().await.as_ref().and_then(|s| s.portal_url.clone());
+    let info = analytics::AlertInfo {
+        level: analytics::AlertLevel::Info,
+        message: "This is a test alert from orb Credit Monitor".to_string(),
+        estimated_time_remaining: None,
+    };
+    let balance = state.database.get_latest_balance().await?.map(|record| record.amount);
+    let alert = chat_channels::ChatAlert::from_alert_info("Test Alert", &info, balance, portal_url);
+
+    let notifications = state.notifications.lock().await;
+    Ok(notifications.send_chat_message(&channel, &alert).await)
+}
+
+/// Send a test email with the given (possibly unsaved) SMTP settings
+#[tauri::command]
+async fn test_email(settings: email::EmailSettings) -> AppResult<()> {
+    tracing::info!("🧪 TEST EMAIL via {}:{}", settings.smtp_host, settings.smtp_port);
+    let settings = email::EmailSettings { enabled: true, ..settings };
+    settings.validate()?;
+
+    email::EmailSender::new(&settings)
+        .send("[orb] Test email", "This is a test email from orb Credit Monitor.")
+        .await
+}
+
+/// Build and email the digest right away, independent of the schedule
+#[tauri::command]
+async fn send_digest_now(state: tauri::State<'_, AppState>) -> AppResult<()> {
+    tracing::info!("📧 SEND DIGEST NOW");
+    let (settings, session_cookie) = {
+        let config = state.config.lock().await;
+        (config.email.clone(), state.fixtures.session_cookie(config.session_cookie.clone()))
+    };
+    if !settings.enabled {
+        return Err(AppError::InvalidInput("Email is not enabled".to_string()));
+    }
+
+    let session_cookie = session_cookie.ok_or_else(|| AppError::Auth("Not logged in".to_string()))?;
+    let client = new_augment_client(&state, session_cookie).await?;
+
+    let settings = email::EmailSettings {
+        digest: if settings.digest == email::DigestSchedule::Off { email::DigestSchedule::Daily } else { settings.digest },
+        ..settings
+    };
+    send_digest(&state, &client, &settings).await
+}
+
+/// Current state of every alert rule (armed, firing, acknowledged, resolved)
+#[tauri::command]
+async fn get_alert_states(state: tauri::State<'_, AppState>) -> AppResult<Vec<alert_state::AlertState>> {
+    state.database.get_alert_states().await
+}
+
+/// Fired alerts, newest first
+#[tauri::command]
+async fn get_alert_history(
+    state: tauri::State<'_, AppState>,
+    limit: Option<u32>,
+    unacknowledged_only: Option<bool>,
+) -> AppResult<Vec<database::AlertHistoryEntry>> {
+    state.database
+        .get_alert_history(limit.unwrap_or(100).min(1000), unacknowledged_only.unwrap_or(false))
+        .await
+}
+
+/// Acknowledge one inbox entry; a still-firing rule is acknowledged along with it
+#[tauri::command]
+async fn acknowledge_alert_entry(state: tauri::State<'_, AppState>, id: String) -> AppResult<bool> {
+    tracing::info!("🔕 ACKNOWLEDGE ALERT ENTRY: {}", id);
+    let id = parse_alert_id(&id)?;
+
+    let entry = match state.database.get_alert_history_entry(id).await? {
+        Some(entry) => entry,
+        None => return Ok(false),
+    };
+
+    let acknowledged = state.database.acknowledge_alert_history(id).await?;
+    acknowledge_alert(state, entry.rule_id).await?;
+    Ok(acknowledged)
+}
+
+#[tauri::command]
+async fn acknowledge_all_alerts(state: tauri::State<'_, AppState>) -> AppResult<u64> {
+    tracing::info!("🔕 ACKNOWLEDGE ALL ALERTS");
+    let count = state.database.acknowledge_all_alert_history().await?;
+
+    let _notifications = state.notifications.lock().await;
+    let mut states = state.database.get_alert_states().await?;
+    let now = chrono::Utc::now();
+    let mut changed = false;
+    for alert in states.iter_mut() {
+        changed |= alert.acknowledge(now);
+    }
+    if changed {
+        state.database.save_alert_states(&states).await?;
+    }
+
+    Ok(count)
+}
+
+/// Silence notifications for the entry's rule for `minutes`
+#[tauri::command]
+async fn snooze_alert(state: tauri::State<'_, AppState>, id: String, minutes: u32) -> AppResult<bool> {
+    tracing::info!("💤 SNOOZE ALERT {} for {} minutes", id, minutes);
+    if minutes == 0 {
+        return Err(AppError::InvalidInput("Snooze duration must be at least one minute".to_string()));
+    }
+
+    let until = chrono::Utc::now() + chrono::Duration::minutes(minutes as i64);
+    state.database.snooze_alert_history(parse_alert_id(&id)?, until).await
+}
+
+fn parse_alert_id(id: &str) -> AppResult<uuid::Uuid> {
+    uuid::Uuid::parse_str(id)
+        .map_err(|_| AppError::InvalidInput(format!("Invalid alert id: {}", id)))
+}
+
+/// Silence a firing alert until it resolves or escalates
+#[tauri::command]
+async fn acknowledge_alert(state: tauri::State<'_, AppState>, rule_id: String) -> AppResult<bool> {
+    tracing::info!("🔕 ACKNOWLEDGE ALERT: {}", rule_id);
+    let _notifications = state.notifications.lock().await;
+
+    let mut states = state.database.get_alert_states().await?;
+    let acknowledged = match states.iter_mut().find(|s| s.rule_id == rule_id) {
+        Some(alert) => alert.acknowledge(chrono::Utc::now()),
+        None => false,
+    };
+
+    if acknowledged {
+        state.database.save_alert_states(&states).await?;
+    }
+    Ok(acknowledged)
+}
+
 #[tauri::command]
 async fn trigger_manual_update(
     state: tauri::State<'_, AppState>,
@@ -159,7 +470,7 @@ async fn trigger_manual_update(
 
         // Store in database
         tracing::info!("💾 Storing balance in database...");
-        state.database.insert_balance_record(balance).await?;
+        ingest_balance(&state, balance, None).await?;
         tracing::info!("✅ Balance stored in database");
 
         // Update system tray
@@ -296,7 +607,7 @@ async fn parse_orb_url(
                 tracing::info!("✅ IMMEDIATE FETCH: Successfully fetched balance: {}", balance);
 
                 // Store in database
-                if let Err(e) = state.database.insert_balance_record(balance).await {
+                if let Err(e) = ingest_balance(&state, balance, None).await {
                     tracing::error!("❌ Failed to store immediate balance in database: {}", e);
                 }
 
@@ -389,47 +700,16 @@ async fn clear_system_tray_command(app_handle: tauri::AppHandle) -> AppResult<()
 /// Save the session cookie from WebView login
 #[tauri::command]
 async fn save_session_cookie(
-    state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
     session_cookie: String,
 ) -> AppResult<serde_json::Value> {
     tracing::info!("🔐 SAVE SESSION COOKIE - Validating and saving...");
-
-    // Create client and validate the session
-    let client = AugmentClient::new(session_cookie.clone())?;
-
-    // Fetch user info to validate and get email
-    let user = client.fetch_user().await?;
-    tracing::info!("✅ Session validated for user: {}", user.email);
-
-    // Save to config
-    {
-        let mut config = state.config.lock().await;
-        config.set_session_cookie(session_cookie, Some(user.email.clone()));
-        config.save().await?;
-    }
-
-    // Immediately fetch balance
-    let credits = client.fetch_credits().await?;
-    let balance = credits.usage_units_remaining as u32;
-
-    // Store in database
-    if let Err(e) = state.database.insert_balance_record(balance).await {
-        tracing::error!("❌ Failed to store balance: {}", e);
-    }
-
-    // Update system tray
-    if let Err(e) = update_system_tray_balance(&app_handle, balance) {
-        tracing::error!("❌ Failed to update tray: {}", e);
-    }
-
-    // Emit event to frontend
-    let _ = app_handle.emit("balance-updated", balance);
+    let login = validate_and_save_session(&app_handle, session_cookie).await?;
 
     Ok(serde_json::json!({
         "success": true,
-        "email": user.email,
-        "balance": balance
+        "email": login.email,
+        "balance": login.balance
     }))
 }
 
@@ -443,19 +723,19 @@ async fn fetch_augment_credits(
 
     let session_cookie = {
         let config = state.config.lock().await;
-        config.session_cookie.clone()
+        state.fixtures.session_cookie(config.session_cookie.clone())
     };
 
     let session_cookie = session_cookie.ok_or_else(|| {
         AppError::Auth("No session configured".to_string())
     })?;
 
-    let client = AugmentClient::new(session_cookie)?;
+    let client = new_augment_client(&state, session_cookie).await?;
     let credits = client.fetch_credits().await?;
     let balance = credits.usage_units_remaining as u32;
 
     // Store in database
-    if let Err(e) = state.database.insert_balance_record(balance).await {
+    if let Err(e) = ingest_balance(&state, balance, Some(&credits)).await {
         tracing::error!("❌ Failed to store balance: {}", e);
     }
 
@@ -478,29 +758,83 @@ async fn fetch_augment_credits(
 #[tauri::command]
 async fn fetch_augment_subscription(
     state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
 ) -> AppResult<serde_json::Value> {
     tracing::info!("🔄 FETCH AUGMENT SUBSCRIPTION");
 
     let session_cookie = {
         let config = state.config.lock().await;
-        config.session_cookie.clone()
+        state.fixtures.session_cookie(config.session_cookie.clone())
     };
 
     let session_cookie = session_cookie.ok_or_else(|| {
         AppError::Auth("No session configured".to_string())
     })?;
 
-    let client = AugmentClient::new(session_cookie)?;
+    let client = new_augment_client(&state, session_cookie).await?;
+
+    // Render from cache right away; a stale copy is refreshed in the background and the
+    // result pushed with "subscription-updated"
+    if let Some((subscription, freshness)) = client.cached_subscription() {
+        if freshness.stale {
+            let state = state.inner().clone();
+            tokio::spawn(async move {
+                match client.fetch_subscription().await {
+                    Ok(subscription) => {
+                        *state.subscription.lock().await = Some(subscription.clone());
+                        let payload = subscription_payload(&subscription, &Freshness::live(chrono::Utc::now()));
+                        let _ = app_handle.emit("subscription-updated", payload);
+                    }
+                    Err(e) => tracing::warn!("⚠️ Background subscription refresh failed: {}", e),
+                }
+            });
+        }
+        return Ok(subscription_payload(&subscription, &freshness));
+    }
+
     let subscription = client.fetch_subscription().await?;
+    *state.subscription.lock().await = Some(subscription.clone());
 
-    Ok(serde_json::json!({
+    Ok(subscription_payload(&subscription, &Freshness::live(chrono::Utc::now())))
+}
+
+fn subscription_payload(subscription: &SubscriptionResponse, freshness: &Freshness) -> serde_json::Value {
+    serde_json::json!({
         "plan_name": subscription.plan_name,
         "billing_period_end": subscription.billing_period_end,
         "credits_included": subscription.credits_included_this_billing_cycle,
         "credits_renewing": subscription.credits_renewing_each_billing_cycle,
         "trial_grant": subscription.trial_grant,
-        "plan_facts": subscription.plan_facts
-    }))
+        "plan_facts": subscription.plan_facts,
+        "freshness": freshness
+    })
+}
+
+/// Schema drift and decode failures seen in Augment API responses
+#[tauri::command]
+async fn get_api_schema_diagnostics(state: tauri::State<'_, AppState>) -> AppResult<Vec<schema_drift::DecodeDiagnostic>> {
+    let session_cookie = state.fixtures.session_cookie(state.config.lock().await.session_cookie.clone()).ok_or_else(|| {
+        AppError::Auth("No session cookie configured".to_string())
+    })?;
+
+    let client = new_augment_client(&state, session_cookie).await?;
+    Ok(client.schema_diagnostics())
+}
+
+/// Credit consumption for an arbitrary range, grouping and granularity
+#[tauri::command]
+async fn query_augment_consumption(
+    state: tauri::State<'_, AppState>,
+    query: consumption_query::ConsumptionQuery,
+) -> AppResult<augment_client::CreditConsumptionResponse> {
+    tracing::info!("🔄 QUERY AUGMENT CONSUMPTION: {:?}", query);
+
+    let session_cookie = state.fixtures.session_cookie(state.config.lock().await.session_cookie.clone()).ok_or_else(|| {
+        AppError::Auth("No session cookie configured".to_string())
+    })?;
+
+    let client = new_augment_client(&state, session_cookie).await?;
+    client.fetch_consumption(&query).await
 }
 
 /// Fetch complete analytics data using new Augment API endpoints
@@ -514,14 +848,14 @@ async fn fetch_augment_analytics(
 
     let session_cookie = {
         let config = state.config.lock().await;
-        config.session_cookie.clone()
+        state.fixtures.session_cookie(config.session_cookie.clone())
     };
 
     let session_cookie = session_cookie.ok_or_else(|| {
         AppError::Auth("No session cookie configured".to_string())
     })?;
 
-    let client = AugmentClient::new(session_cookie)?;
+    let client = new_augment_client(&state, session_cookie).await?;
 
     // Fetch all data in parallel
     let (analytics_info, daily_consumption, model_consumption, activity_consumption) = tokio::join!(
@@ -566,6 +900,19 @@ async fn fetch_augment_analytics(
             vec![]
         });
 
+    // Keep server-side daily consumption for statistics and forecasting
+    let now = chrono::Utc::now();
+    let consumption_records: Vec<database::DailyConsumptionRecord> = daily_usage.iter()
+        .map(|d| database::DailyConsumptionRecord {
+            date: d.date.clone(),
+            credits: d.total_credits,
+            updated_at: now,
+        })
+        .collect();
+    if let Err(e) = state.database.upsert_daily_consumption(&consumption_records).await {
+        tracing::error!("❌ Failed to store daily consumption: {}", e);
+    }
+
     // Calculate summary stats from daily usage
     let total_credits_used: i64 = daily_usage.iter().map(|d| d.total_credits).sum();
     let days_with_data = daily_usage.len();
@@ -600,6 +947,39 @@ async fn fetch_augment_analytics(
     }))
 }
 
+/// Moving averages, percentiles and busiest periods from local and stored server history
+#[tauri::command]
+async fn get_usage_statistics(
+    state: tauri::State<'_, AppState>,
+    days: Option<u32>,
+    source: Option<statistics::StatisticsSource>,
+) -> AppResult<statistics::UsageStatistics> {
+    let days = days.unwrap_or(90);
+    let source = source.unwrap_or_default();
+    tracing::info!("📊 GET USAGE STATISTICS (last {} days, {:?})", days, source);
+
+    let session_cookie = {
+        let config = state.config.lock().await;
+        state.fixtures.session_cookie(config.session_cookie.clone())
+    };
+
+    // The billing cycle boundary comes from the subscription when we can reach it
+    let mut cycle_start = None;
+    if let Some(session_cookie) = session_cookie {
+        match new_augment_client(&state, session_cookie).await?.fetch_subscription().await {
+            Ok(subscription) => {
+                cycle_start = statistics::cycle_start_from_billing_period_end(&subscription.billing_period_end);
+                *state.subscription.lock().await = Some(subscription);
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to fetch subscription for cycle start: {}", e);
+            }
+        }
+    }
+
+    state.analytics.calculate_usage_statistics(days, source, cycle_start).await
+}
+
 /// Get current auth status
 #[tauri::command]
 async fn get_auth_status(
@@ -635,6 +1015,8 @@ async fn clear_augment_session(
         config.clear_augment_session();
         config.save().await?;
     }
+    state.session.lock().await.reset();
+    state.augment_clients.lock().await.clear();
 
     // Clear system tray
     let _ = clear_system_tray(&app_handle);
@@ -643,31 +1025,47 @@ async fn clear_augment_session(
     Ok(())
 }
 
-/// Helper function to validate and save session from the login WebView
-async fn validate_and_save_session(app_handle: &tauri::AppHandle, session_cookie: String) -> AppResult<()> {
-    tracing::info!("🔐 Validating session cookie...");
+/// Account a successful login signed in to
+struct LoginResult {
+    email: String,
+    balance: u32,
+}
 
-    // Validate the session by fetching user info
-    let client = AugmentClient::new(session_cookie.clone())?;
-    let user = client.fetch_user().await?;
-    tracing::info!("✅ Session validated for user: {}", user.email);
+/// Validate and save a session cookie, however the user logged in. Every login path goes
+/// through here so an expired session is always restored.
+async fn validate_and_save_session(app_handle: &tauri::AppHandle, session_cookie: String) -> AppResult<LoginResult> {
+    tracing::info!("🔐 Validating session cookie...");
 
     // Get state from app handle
     let state = app_handle.state::<AppState>();
 
+    // Validate the session by fetching user info
+    let client = new_augment_client(&state, session_cookie.clone()).await?;
+    let user = client.fetch_user_uncached().await?;
+    tracing::info!("✅ Session validated for user: {}", user.email);
+
     // Save to config
     {
         let mut config = state.config.lock().await;
-        config.set_session_cookie(session_cookie, Some(user.email.clone()));
+        config.set_session_cookie(session_cookie.clone(), Some(user.email.clone()));
         config.save().await?;
     }
 
+    // A fresh login ends an expired session; the monitoring loop resumes on its next tick
+    if state.session.lock().await.record_success() == SessionChange::Restored {
+        tracing::info!("🔓 Session restored, resuming monitoring");
+        let _ = app_handle.emit("session-restored", ());
+    }
+
+    // An empty history means this is the first login, so charts need a backfill
+    let needs_backfill = state.database.get_earliest_live_balance_timestamp().await?.is_none();
+
     // Fetch initial balance
     let credits = client.fetch_credits().await?;
     let balance = credits.usage_units_remaining as u32;
 
     // Store in database
-    if let Err(e) = state.database.insert_balance_record(balance).await {
+    if let Err(e) = ingest_balance(&state, balance, Some(&credits)).await {
         tracing::error!("❌ Failed to store balance: {}", e);
     }
 
@@ -680,11 +1078,82 @@ async fn validate_and_save_session(app_handle: &tauri::AppHandle, session_cookie
     let _ = app_handle.emit("balance-updated", balance);
     let _ = app_handle.emit("config-changed", ());
 
+    if needs_backfill {
+        spawn_history_backfill(app_handle.clone(), session_cookie);
+    }
+
+    Ok(LoginResult { email: user.email, balance })
+}
+
+/// Reconstruct balance history in the background, reporting progress as `backfill-progress` events
+fn spawn_history_backfill(app_handle: tauri::AppHandle, session_cookie: String) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("🕰️ Starting balance history backfill...");
+
+        let state = app_handle.state::<AppState>();
+        let max_days = {
+            let config = state.config.lock().await;
+            config.data_retention_days
+        };
+
+        let client = match new_augment_client(&state, session_cookie).await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("❌ Failed to create Augment client for backfill: {}", e);
+                return;
+            }
+        };
+
+        let progress_handle = app_handle.clone();
+        let result = backfill::run_backfill(&client, &state.database, max_days, move |progress| {
+            let _ = progress_handle.emit("backfill-progress", progress);
+        }).await;
+
+        match result {
+            Ok(summary) => {
+                tracing::info!("✅ Backfill finished: {} points", summary.points_inserted);
+                let _ = app_handle.emit("history-backfilled", summary);
+
+                // Enough history now exists to pick a forecaster for this user
+                match state.analytics.run_forecast_backtest(max_days, 7).await {
+                    Ok(report) => {
+                        if let Err(e) = auto_select_forecast_method(&state, &report).await {
+                            tracing::error!("❌ Failed to save forecast method: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::error!("❌ Forecast backtest failed: {}", e),
+                }
+            }
+            Err(e) => {
+                tracing::error!("❌ Backfill failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Re-run the balance history backfill (replaces previously reconstructed records)
+#[tauri::command]
+async fn start_history_backfill(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> AppResult<()> {
+    tracing::info!("🕰️ START HISTORY BACKFILL");
+
+    let session_cookie = {
+        let config = state.config.lock().await;
+        state.fixtures.session_cookie(config.session_cookie.clone())
+    };
+
+    let session_cookie = session_cookie.ok_or_else(|| {
+        AppError::Auth("No session configured".to_string())
+    })?;
+
+    spawn_history_backfill(app_handle, session_cookie);
     Ok(())
 }
 
 /// Open a WebView window for Augment login
-/// This creates a new window that loads app.augmentcode.com
+/// This creates a new window that loads the configured login URL (app.augmentcode.com by default)
 /// After login, JavaScript extracts the _session cookie and sends it back
 #[tauri::command]
 async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
@@ -699,6 +1168,10 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
         return Ok(());
     }
 
+    let endpoints = augment_endpoints(&app_handle.state::<AppState>()).await;
+    let login_url = endpoints.login_url()?;
+    let endpoints_for_load = endpoints.clone();
+
     let app_handle_clone = app_handle.clone();
     let app_handle_for_nav = app_handle.clone();
 
@@ -706,7 +1179,7 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
     let login_window = WebviewWindowBuilder::new(
         &app_handle,
         "augment-login",
-        WebviewUrl::External("https://app.augmentcode.com".parse().unwrap()),
+        WebviewUrl::External(login_url.clone()),
     )
     .title("Login to Augment")
     .inner_size(480.0, 700.0)
@@ -716,7 +1189,7 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
         let url_str = url.as_str();
 
         // Intercept our special path for cookie extraction (no custom protocol = no OS dialog)
-        if url_str.contains("/__tauri_extract_session__") {
+        if url_str.contains("/__tauri_extract_session__") && endpoints.is_login_origin(url) {
             tracing::info!("🔗 Intercepted session extraction request");
             let app_handle = app_handle_for_nav.clone();
 
@@ -724,8 +1197,7 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
             tracing::info!("🔒 Extracting session cookie from WebView cookie store...");
 
             if let Some(login_win) = app_handle.get_webview_window("augment-login") {
-                let augment_url = url::Url::parse("https://app.augmentcode.com").unwrap();
-                match login_win.cookies_for_url(augment_url) {
+                match login_win.cookies_for_url(login_url.clone()) {
                     Ok(cookies) => {
                         tracing::info!("🍪 Found {} cookies", cookies.len());
 
@@ -773,9 +1245,10 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
             let url = payload.url().to_string();
             tracing::info!("📄 Page loaded: {}", url);
 
-            // If we're on app.augmentcode.com (not login page), try to extract cookie
-            if url.starts_with("https://app.augmentcode.com") && !url.contains("login") && !url.contains("auth") {
-                tracing::info!("🎉 User is on app.augmentcode.com - injecting cookie extraction UI...");
+            // If we're on the Augment app (not login page), try to extract cookie
+            let path = payload.url().path();
+            if endpoints_for_load.is_login_origin(payload.url()) && !path.contains("login") && !path.contains("auth") {
+                tracing::info!("🎉 User is on the Augment app - injecting cookie extraction UI...");
 
                 let app_handle_for_js = app_handle_clone.clone();
                 let webview_clone = webview.clone();
@@ -880,7 +1353,7 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
 
                                 // Navigate to a page on the same domain with special path
                                 // This won't trigger external app dialog
-                                window.location.href = 'https://app.augmentcode.com/__tauri_extract_session__';
+                                window.location.href = window.location.origin + '/__tauri_extract_session__';
                             });
 
                             // Hover effect
@@ -917,43 +1390,15 @@ async fn open_augment_login(app_handle: tauri::AppHandle) -> AppResult<()> {
 /// Receive cookie from the login WebView
 #[tauri::command]
 async fn receive_login_cookie(
-    state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
     session_cookie: String,
 ) -> AppResult<serde_json::Value> {
     tracing::info!("🍪 RECEIVED LOGIN COOKIE from WebView");
+    let login = validate_and_save_session(&app_handle, session_cookie).await?;
 
-    // Validate the session by fetching user info
-    let client = AugmentClient::new(session_cookie.clone())?;
-    let user = client.fetch_user().await?;
-    tracing::info!("✅ Session validated for user: {}", user.email);
-
-    // Save to config
-    {
-        let mut config = state.config.lock().await;
-        config.set_session_cookie(session_cookie, Some(user.email.clone()));
-        config.save().await?;
-    }
-
-    // Fetch initial balance
-    let credits = client.fetch_credits().await?;
-    let balance = credits.usage_units_remaining as u32;
-
-    // Store in database
-    if let Err(e) = state.database.insert_balance_record(balance).await {
-        tracing::error!("❌ Failed to store balance: {}", e);
-    }
-
-    // Update system tray
-    if let Err(e) = update_system_tray_balance(&app_handle, balance) {
-        tracing::error!("❌ Failed to update tray: {}", e);
-    }
-
-    // Emit events
-    let _ = app_handle.emit("balance-updated", balance);
     let _ = app_handle.emit("login-complete", serde_json::json!({
-        "email": user.email,
-        "balance": balance
+        "email": login.email,
+        "balance": login.balance
     }));
 
     // Close the login window
@@ -972,8 +1417,8 @@ async fn receive_login_cookie(
 
     Ok(serde_json::json!({
         "success": true,
-        "email": user.email,
-        "balance": balance
+        "email": login.email,
+        "balance": login.balance
     }))
 }
 
@@ -982,10 +1427,11 @@ fn create_system_tray(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::
     let separator1 = MenuItem::with_id(app, "separator1", "---", false, None::<&str>)?;
     let show = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
     let hide = MenuItem::with_id(app, "hide", "Hide Window", true, None::<&str>)?;
+    let login = MenuItem::with_id(app, "login", "Log In to Augment", true, None::<&str>)?;
     let separator2 = MenuItem::with_id(app, "separator2", "---", false, None::<&str>)?;
     let quit = MenuItem::with_id(app, "quit", "Quit Application", true, None::<&str>)?;
 
-    let menu = Menu::with_items(app, &[&balance, &separator1, &show, &hide, &separator2, &quit])?;
+    let menu = Menu::with_items(app, &[&balance, &separator1, &show, &hide, &login, &separator2, &quit])?;
 
     let _tray = TrayIconBuilder::with_id("main-tray")
         .menu(&menu)
@@ -1008,6 +1454,14 @@ fn create_system_tray(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::
                         }
                     }
                 }
+                "login" => {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = open_augment_login(app).await {
+                            tracing::error!("❌ Failed to open login window: {}", e);
+                        }
+                    });
+                }
                 "hide" => {
                     if let Some(window) = app.get_webview_window("main") {
                         let _ = window.hide();
@@ -1064,15 +1518,24 @@ async fn setup_app_state() -> AppResult<AppState> {
     // Initialize database
     let database = Arc::new(Database::new().await?);
     
-    // Initialize scraper
-    let scraper = Arc::new(orbScraper::new().await?);
+    // Recording / replaying API traffic, shared by every client
+    let fixtures = Arc::new(FixtureStore::from_env());
+
+    // Initialize scraper; update_config rebuilds it when the network settings change
+    let network = config.lock().await.network.clone();
+    let scraper = Arc::new(ScraperHandle::new(&network, fixtures.clone()).await);
     
     // Initialize analytics engine
     let analytics = Arc::new(AnalyticsEngine::new(database.clone()));
     
     // Initialize notification manager
-    let notifications = Arc::new(Mutex::new(NotificationManager::new()));
-    
+    let mut notification_manager = NotificationManager::new();
+    notification_manager.configure(&config.lock().await);
+    notification_manager.attach_database(database.clone()).await;
+    let notifications = Arc::new(Mutex::new(notification_manager));
+    let session = SessionTracker::new(config.lock().await.session_expiry_failures);
+    let connectivity = ConnectivityTracker::new(config.lock().await.connection_lost_after_minutes);
+
     Ok(AppState {
         config,
         database,
@@ -1080,6 +1543,12 @@ async fn setup_app_state() -> AppResult<AppState> {
         analytics,
         notifications,
         window_visible: Arc::new(Mutex::new(true)), // Start with window visible
+        last_mix_check: Arc::new(Mutex::new(None)),
+        subscription: Arc::new(Mutex::new(None)),
+        session: Arc::new(Mutex::new(session)),
+        connectivity: Arc::new(Mutex::new(connectivity)),
+        augment_clients: Arc::new(Mutex::new(AugmentClientCache::new(fixtures.clone()))),
+        fixtures,
     })
 }
 
@@ -1100,6 +1569,23 @@ async fn main() -> AppResult<()> {
             get_current_balance,
             get_usage_analytics,
             update_config,
+            get_quiet_hours_status,
+            test_alert_sound,
+            get_notification_deliveries,
+            get_session_status,
+            get_connectivity_status,
+            get_alert_rules,
+            save_alert_rules,
+            get_alert_states,
+            test_webhook,
+            test_chat_channel,
+            test_email,
+            send_digest_now,
+            acknowledge_alert,
+            get_alert_history,
+            acknowledge_alert_entry,
+            acknowledge_all_alerts,
+            snooze_alert,
             trigger_manual_update,
             update_tray_balance,
             fetch_fresh_balance,
@@ -1116,10 +1602,15 @@ async fn main() -> AppResult<()> {
             fetch_augment_credits,
             fetch_augment_subscription,
             fetch_augment_analytics,
+            query_augment_consumption,
+            get_api_schema_diagnostics,
+            get_usage_statistics,
+            run_forecast_backtest,
             get_auth_status,
             clear_augment_session,
             open_augment_login,
-            receive_login_cookie
+            receive_login_cookie,
+            start_history_backfill
         ])
         .setup(|app| {
             // Create system tray
@@ -1182,23 +1673,36 @@ async fn monitoring_loop(state: AppState, app_handle: tauri::AppHandle) {
         interval.tick().await;
         tracing::info!("⏰ MONITORING LOOP TICK - Starting new cycle");
 
+        // Deliver the summary of anything held back once quiet hours are over
+        state.notifications.lock().await.flush_deferred().await;
+
         // Check auth method and get credentials
         let (session_cookie, orb_token) = {
             let config = state.config.lock().await;
-            (config.session_cookie.clone(), config.orb_token.clone())
+            (state.fixtures.session_cookie(config.session_cookie.clone()), config.orb_token.clone())
         };
 
+        let session_expired = state.session.lock().await.is_expired();
+
+        if session_cookie.is_some() && session_expired {
+            // Polling with a dead cookie only repeats the same auth error
+            tracing::warn!("🔒 Background monitoring: Session expired, waiting for re-login");
+        }
         // Priority: Use new Augment API if session cookie is available
-        if let Some(session_cookie) = session_cookie {
+        else if let Some(session_cookie) = session_cookie {
             tracing::info!("🔄 Background monitoring: Using Augment API...");
-            match AugmentClient::new(session_cookie) {
+            match new_augment_client(&state, session_cookie).await {
                 Ok(client) => {
                     match client.fetch_credits().await {
                         Ok(credits) => {
                             let balance = credits.usage_units_remaining as u32;
                             tracing::info!("✅ Background monitoring: Augment credits: {}", balance);
+                            state.session.lock().await.record_success();
+                            record_connectivity(&state, &app_handle, "Augment", None).await;
+
+                            refresh_subscription_if_stale(&state, &client).await;
 
-                            if let Err(e) = state.database.insert_balance_record(balance).await {
+                            if let Err(e) = ingest_balance(&state, balance, Some(&credits)).await {
                                 tracing::error!("❌ Failed to insert balance record: {}", e);
                             }
 
@@ -1210,14 +1714,16 @@ async fn monitoring_loop(state: AppState, app_handle: tauri::AppHandle) {
                                 tracing::error!("❌ Failed to emit balance event: {}", e);
                             }
 
-                            // Check for alerts
-                            if let Ok(analytics) = state.analytics.calculate_usage_analytics(24).await {
-                                let mut notifications = state.notifications.lock().await;
-                                notifications.check_and_send_alerts(&analytics, balance).await;
-                            }
+                            check_mix_changes(&state, &app_handle, &client).await;
+                            send_digest_if_due(&state, &client).await;
                         }
                         Err(e) => {
                             tracing::error!("❌ Augment API error: {}", e);
+                            if e.is_auth_failure() {
+                                handle_auth_failure(&state, &app_handle).await;
+                            } else if let Some(kind) = connectivity::classify(&e) {
+                                record_connectivity(&state, &app_handle, "Augment", Some(kind)).await;
+                            }
                         }
                     }
                 }
@@ -1232,8 +1738,9 @@ async fn monitoring_loop(state: AppState, app_handle: tauri::AppHandle) {
             match state.scraper.fetch_balance(&token).await {
                 Ok(balance) => {
                     tracing::info!("✅ Background monitoring (Orb): balance: {}", balance);
+                    record_connectivity(&state, &app_handle, "Orb", None).await;
 
-                    if let Err(e) = state.database.insert_balance_record(balance).await {
+                    if let Err(e) = ingest_balance(&state, balance, None).await {
                         tracing::error!("❌ Failed to insert balance record: {}", e);
                     }
 
@@ -1244,14 +1751,12 @@ async fn monitoring_loop(state: AppState, app_handle: tauri::AppHandle) {
                     if let Err(e) = app_handle.emit("balance-updated", balance) {
                         tracing::error!("❌ Failed to emit balance event: {}", e);
                     }
-
-                    if let Ok(analytics) = state.analytics.calculate_usage_analytics(24).await {
-                        let mut notifications = state.notifications.lock().await;
-                        notifications.check_and_send_alerts(&analytics, balance).await;
-                    }
                 }
                 Err(e) => {
                     tracing::error!("❌ Orb scraper error: {}", e);
+                    if let Some(kind) = connectivity::classify(&e) {
+                        record_connectivity(&state, &app_handle, "Orb", Some(kind)).await;
+                    }
                 }
             }
         } else {
@@ -1266,6 +1771,464 @@ async fn monitoring_loop(state: AppState, app_handle: tauri::AppHandle) {
     }
 }
 
+/// Track whether `provider` is reachable. `failure` is `None` after a successful fetch.
+/// Alerts once when an outage has lasted long enough and once when it ends.
+async fn record_connectivity(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    provider: &str,
+    failure: Option<connectivity::FailureKind>,
+) {
+    let now = chrono::Utc::now();
+    let (was_online, change, status) = {
+        let mut tracker = state.connectivity.lock().await;
+        let was_online = matches!(tracker.status(), connectivity::ConnectivityStatus::Online);
+        let change = match failure {
+            Some(kind) => tracker.record_failure(kind, now),
+            None => tracker.record_success(now),
+        };
+        (was_online, change, tracker.status())
+    };
+
+    if was_online && failure.is_none() {
+        return;
+    }
+
+    if let Err(e) = app_handle.emit("connectivity-changed", &status) {
+        tracing::error!("❌ Failed to emit connectivity event: {}", e);
+    }
+
+    match &change {
+        ConnectivityChange::Unchanged => return,
+        ConnectivityChange::Lost { since, kind } => {
+            tracing::warn!("📡 Connection lost since {} ({})", since, kind.label());
+            if let Err(e) = show_connection_lost_in_tray(app_handle, kind) {
+                tracing::error!("❌ Failed to update system tray: {}", e);
+            }
+        }
+        ConnectivityChange::Restored { outage } => {
+            tracing::info!("📡 Connection restored after {}", connectivity::describe_outage(*outage));
+        }
+    }
+
+    if state.config.lock().await.enable_notifications {
+        let mut notifications = state.notifications.lock().await;
+        if let Err(e) = notifications.send_connection_status(provider, &change).await {
+            tracing::error!("Failed to send connection notification: {}", e);
+        }
+    }
+}
+
+/// Endpoints of the configured Augment account
+async fn augment_endpoints(state: &AppState) -> augment_client::AugmentEndpoints {
+    state.config.lock().await.augment_endpoints.clone()
+}
+
+/// Shared client for the configured Augment account; a new one is only built when the session
+/// cookie, endpoints, retry policy, cache or network settings change
+async fn new_augment_client(state: &AppState, session_cookie: String) -> AppResult<Arc<AugmentClient>> {
+    let (endpoints, retry, cache, network) = {
+        let config = state.config.lock().await;
+        (
+            config.augment_endpoints.clone(),
+            config.http_retry.clone(),
+            config.http_cache.clone(),
+            config.network.clone(),
+        )
+    };
+    state.augment_clients.lock().await.get(&session_cookie, &endpoints, &retry, &cache, &network)
+}
+
+/// Count an auth failure; once the session is considered expired, show it in the tray and
+/// send a single notification asking the user to log in again
+async fn handle_auth_failure(state: &AppState, app_handle: &tauri::AppHandle) {
+    let (change, status) = {
+        let mut session = state.session.lock().await;
+        let change = session.record_auth_failure(chrono::Utc::now());
+        (change, session.status())
+    };
+    if change != SessionChange::Expired {
+        return;
+    }
+
+    tracing::warn!("🔒 Session marked as expired after repeated auth failures");
+
+    if let Err(e) = show_session_expired_in_tray(app_handle) {
+        tracing::error!("❌ Failed to update system tray: {}", e);
+    }
+
+    if let Err(e) = app_handle.emit("session-expired", status) {
+        tracing::error!("❌ Failed to emit session event: {}", e);
+    }
+
+    if state.config.lock().await.enable_notifications {
+        if let Err(e) = state.notifications.lock().await.send_session_expired().await {
+            tracing::error!("Failed to send session expiry notification: {}", e);
+        }
+    }
+}
+
+/// Store a freshly fetched balance and run the alert rules against it
+async fn ingest_balance(state: &AppState, balance: u32, credits: Option<&CreditsResponse>) -> AppResult<()> {
+    state.database.insert_balance_record(balance).await?;
+    evaluate_alert_rules(state, balance, credits).await;
+    Ok(())
+}
+
+async fn evaluate_alert_rules(state: &AppState, balance: u32, credits: Option<&CreditsResponse>) {
+    let (rules, forecast_method, notifications_enabled, behavior) = {
+        let config = state.config.lock().await;
+        (
+            config.effective_alert_rules(),
+            config.forecast_method,
+            config.enable_notifications,
+            config.alert_behavior.clone(),
+        )
+    };
+
+    let mut analytics = match state.analytics.calculate_usage_analytics(24).await {
+        Ok(analytics) => analytics,
+        Err(e) => {
+            tracing::error!("❌ Failed to calculate analytics for alert rules: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = state.analytics.apply_forecast_method(&mut analytics, forecast_method).await {
+        tracing::warn!("⚠️ Failed to apply forecast method: {}", e);
+    }
+
+    let (billing_period_end, portal_url) = {
+        let subscription = state.subscription.lock().await;
+        (
+            subscription.as_ref().map(|s| s.billing_period_end.clone()),
+            subscription.as_ref().and_then(|s| s.portal_url.clone()),
+        )
+    };
+
+    let context = alert_rules::AlertContext {
+        balance,
+        cycle_allotment: credits.map(|c| c.usage_units_remaining + c.usage_units_consumed_this_billing_cycle),
+        cycle_consumed: credits.map(|c| c.usage_units_consumed_this_billing_cycle),
+        cycle_elapsed_fraction: billing_period_end
+            .and_then(|end| statistics::cycle_elapsed_fraction(&end, chrono::Utc::now())),
+        hours_to_depletion: analytics.estimated_hours_remaining,
+        burn_rate_per_hour: analytics.usage_rate_per_hour,
+        average_usage: Some(analytics.average_session_usage),
+    };
+
+    let evaluations = alert_rules::evaluate_rules(&rules, &context);
+
+    // Holding the notification lock serializes concurrent evaluations of the stored states
+    let mut notifications = state.notifications.lock().await;
+
+    let mut states: HashMap<String, alert_state::AlertState> = match state.database.get_alert_states().await {
+        Ok(states) => states.into_iter().map(|s| (s.rule_id.clone(), s)).collect(),
+        Err(e) => {
+            tracing::error!("❌ Failed to load alert states: {}", e);
+            return;
+        }
+    };
+
+    let transitions = alert_state::advance_all(&mut states, &rules, &evaluations, &behavior, chrono::Utc::now());
+
+    let states: Vec<alert_state::AlertState> = states.into_values().collect();
+    if let Err(e) = state.database.save_alert_states(&states).await {
+        tracing::error!("❌ Failed to save alert states: {}", e);
+    }
+
+    for transition in transitions.iter().filter(|t| t.kind != alert_state::TransitionKind::Resolved) {
+        if let Err(e) = state.database.insert_alert_history(
+            &transition.rule_id,
+            &transition.rule_name,
+            transition.severity,
+            &transition.message,
+            Some(balance),
+        ).await {
+            tracing::error!("❌ Failed to record alert history: {}", e);
+        }
+    }
+
+    let snoozed = snoozed_rule_ids(state).await;
+
+    let batch = notifications::AlertBatch {
+        rules: &rules,
+        evaluations: &evaluations,
+        transitions: &transitions,
+        snoozed_rule_ids: &snoozed,
+        balance: Some(balance),
+        hours_to_depletion: analytics.estimated_hours_remaining,
+        portal_url,
+    };
+    notifications.process_alert_transitions(&batch, notifications_enabled).await;
+}
+
+/// Rules whose alerts were snoozed from the alert history and haven't woken up yet
+async fn snoozed_rule_ids(state: &AppState) -> HashSet<String> {
+    state.database.get_snoozed_rule_ids(chrono::Utc::now()).await
+        .unwrap_or_else(|e| {
+            tracing::warn!("⚠️ Failed to load snoozed alerts: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Keep the subscription around for cycle-based alert rules, refreshing it once the cycle ends
+async fn refresh_subscription_if_stale(state: &AppState, client: &AugmentClient) {
+    let previous = load_subscription_snapshot(state).await;
+    let is_stale = {
+        let subscription = state.subscription.lock().await;
+        match (subscription.as_ref(), previous.as_ref()) {
+            (Some(subscription), Some(snapshot)) => {
+                let period_ended = chrono::DateTime::parse_from_rfc3339(&subscription.billing_period_end)
+                    .map(|end| end < chrono::Utc::now())
+                    .unwrap_or(true);
+                period_ended || chrono::Utc::now() - snapshot.taken_at > chrono::Duration::hours(SUBSCRIPTION_CHECK_HOURS)
+            }
+            _ => true,
+        }
+    };
+
+    if is_stale {
+        match client.fetch_subscription().await {
+            Ok(subscription) => {
+                check_subscription_changes(state, previous.as_ref(), &subscription).await;
+                *state.subscription.lock().await = Some(subscription);
+            }
+            Err(e) => tracing::warn!("⚠️ Failed to refresh subscription: {}", e),
+        }
+    }
+}
+
+const SUBSCRIPTION_SNAPSHOT_KEY: &str = "subscription_snapshot";
+/// How often the subscription is re-fetched to look for lifecycle changes
+const SUBSCRIPTION_CHECK_HOURS: i64 = 6;
+
+async fn load_subscription_snapshot(state: &AppState) -> Option<subscription_watch::SubscriptionSnapshot> {
+    match state.database.get_metadata(SUBSCRIPTION_SNAPSHOT_KEY).await {
+        Ok(value) => value.and_then(|json| serde_json::from_str(&json).ok()),
+        Err(e) => {
+            tracing::warn!("⚠️ Failed to load subscription snapshot: {}", e);
+            None
+        }
+    }
+}
+
+/// Diff the subscription against the last snapshot, notify about lifecycle events and store
+/// the new snapshot
+async fn check_subscription_changes(
+    state: &AppState,
+    previous: Option<&subscription_watch::SubscriptionSnapshot>,
+    subscription: &SubscriptionResponse,
+) {
+    let now = chrono::Utc::now();
+    let current = subscription_watch::SubscriptionSnapshot::from_response(subscription, now);
+    let (settings, notifications_enabled) = {
+        let config = state.config.lock().await;
+        (config.subscription_alerts.clone(), config.enable_notifications)
+    };
+
+    if settings.enabled {
+        let events = subscription_watch::diff(previous, &current, &settings, now);
+
+        for event in &events {
+            tracing::info!("📋 Subscription event: {} - {}", event.title, event.message);
+            if let Err(e) = state.database.insert_alert_history(
+                &format!("subscription_{}", event.kind.as_str()),
+                &event.title,
+                event.level,
+                &event.message,
+                None,
+            ).await {
+                tracing::error!("❌ Failed to record alert history: {}", e);
+            }
+        }
+
+        if notifications_enabled && !events.is_empty() {
+            state.notifications.lock().await.send_subscription_events(&events).await;
+        }
+    }
+
+    match serde_json::to_string(&current) {
+        Ok(json) => {
+            if let Err(e) = state.database.set_metadata(SUBSCRIPTION_SNAPSHOT_KEY, &json).await {
+                tracing::error!("❌ Failed to store subscription snapshot: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("❌ Failed to serialize subscription snapshot: {}", e),
+    }
+}
+
+const LAST_DIGEST_KEY: &str = "last_digest_sent_at";
+
+/// Email the daily/weekly digest when its scheduled time has passed since the last one
+async fn send_digest_if_due(state: &AppState, client: &AugmentClient) {
+    let settings = state.config.lock().await.email.clone();
+    if !settings.enabled || settings.digest == email::DigestSchedule::Off {
+        return;
+    }
+
+    let last_sent = match state.database.get_metadata(LAST_DIGEST_KEY).await {
+        Ok(value) => value
+            .and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok())
+            .map(|t| t.with_timezone(&chrono::Local).naive_local()),
+        Err(e) => {
+            tracing::warn!("⚠️ Failed to read last digest time: {}", e);
+            return;
+        }
+    };
+
+    if !email::digest_due(&settings, last_sent, chrono::Local::now().naive_local()) {
+        return;
+    }
+
+    match send_digest(state, client, &settings).await {
+        Ok(()) => {
+            if let Err(e) = state.database.set_metadata(LAST_DIGEST_KEY, &chrono::Utc::now().to_rfc3339()).await {
+                tracing::warn!("⚠️ Failed to record digest time: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("❌ Failed to send digest: {}", e),
+    }
+}
+
+async fn send_digest(state: &AppState, client: &AugmentClient, settings: &email::EmailSettings) -> AppResult<()> {
+    let report = build_digest(state, client, settings.digest).await?;
+    let (subject, body) = email::render_digest(&report);
+    email::EmailSender::new(settings).send(&subject, &body).await
+}
+
+async fn build_digest(
+    state: &AppState,
+    client: &AugmentClient,
+    schedule: email::DigestSchedule,
+) -> AppResult<email::DigestReport> {
+    let period_days = match schedule {
+        email::DigestSchedule::Weekly => 7,
+        _ => 1,
+    };
+
+    let (models, activities) = tokio::join!(
+        client.fetch_consumption_by_model(period_days),
+        client.fetch_consumption_by_activity(period_days)
+    );
+    let by_model = models.map(|c| client.to_model_usage(&c)).unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Digest: failed to fetch usage by model: {}", e);
+        Vec::new()
+    });
+    let by_activity = activities.map(|c| client.to_activity_usage(&c)).unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Digest: failed to fetch usage by activity: {}", e);
+        Vec::new()
+    });
+
+    let forecast_method = state.config.lock().await.forecast_method;
+    let mut analytics = state.analytics.calculate_usage_analytics(period_days * 24).await?;
+    state.analytics.apply_forecast_method(&mut analytics, forecast_method).await?;
+
+    let forecast = (analytics.usage_rate_per_day > 0.0 || analytics.estimated_days_remaining.is_some())
+        .then(|| email::DigestForecast {
+            method: analytics.forecast_method,
+            daily_rate: analytics.usage_rate_per_day,
+            days_remaining: analytics.estimated_days_remaining,
+        });
+
+    let balance = match analytics.current_balance {
+        Some(balance) => Some(balance),
+        None => state.database.get_latest_balance().await?.map(|record| record.amount),
+    };
+
+    let portal_url = state.subscription.lock().await.as_ref().and_then(|s| s.portal_url.clone());
+
+    Ok(email::DigestReport {
+        schedule,
+        generated_at: chrono::Utc::now(),
+        period_days,
+        balance,
+        period_usage: by_model.iter().map(|m| m.credits).sum(),
+        by_model,
+        by_activity,
+        forecast,
+        portal_url,
+    })
+}
+
+/// Compare yesterday's model and activity mix with the rolling baseline, once per day
+async fn check_mix_changes(state: &AppState, app_handle: &tauri::AppHandle, client: &AugmentClient) {
+    let (settings, notifications_enabled) = {
+        let config = state.config.lock().await;
+        (config.mix_alerts.clone(), config.enable_notifications)
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let day = chrono::Utc::now().date_naive() - chrono::Duration::days(1);
+    if *state.last_mix_check.lock().await == Some(day) {
+        return;
+    }
+
+    tracing::info!("🔍 Checking model/activity mix for {}", day);
+    let days = settings.baseline_days + 2;
+    let (models, activities) = tokio::join!(
+        client.fetch_daily_consumption_by_model(days),
+        client.fetch_daily_consumption_by_activity(days)
+    );
+
+    let mut alerts = Vec::new();
+    for (dimension, consumption) in [
+        (mix_alerts::MixDimension::Model, models),
+        (mix_alerts::MixDimension::Activity, activities),
+    ] {
+        match consumption {
+            Ok(consumption) => alerts.extend(mix_alerts::detect_mix_changes(
+                dimension,
+                &client.to_daily_group_usage(&consumption),
+                day,
+                settings.baseline_days,
+                settings.share_shift_threshold_percent,
+                settings.min_daily_credits,
+            )),
+            Err(e) => {
+                // Leave the day unchecked so the next tick retries
+                tracing::warn!("⚠️ Failed to fetch {:?} mix: {}", dimension, e);
+                return;
+            }
+        }
+    }
+
+    *state.last_mix_check.lock().await = Some(day);
+
+    if !alerts.is_empty() {
+        tracing::info!("📣 {} mix change(s) detected", alerts.len());
+        let _ = app_handle.emit("mix-change-alerts", &alerts);
+
+        for alert in &alerts {
+            if let Err(e) = state.database.insert_alert_history(
+                &alert.rule_id(),
+                &alert.title(),
+                alert.level(),
+                &alert.message(),
+                None,
+            ).await {
+                tracing::error!("❌ Failed to record alert history: {}", e);
+            }
+        }
+
+        if notifications_enabled {
+            let snoozed = snoozed_rule_ids(state).await;
+            let (snoozed_alerts, alerts): (Vec<_>, Vec<_>) = alerts.into_iter()
+                .partition(|alert| snoozed.contains(&alert.rule_id()));
+            for alert in &snoozed_alerts {
+                tracing::info!("💤 Alert snoozed, not notifying: {}", alert.title());
+            }
+
+            let mut notifications = state.notifications.lock().await;
+            notifications.send_mix_change_alerts(&alerts).await;
+        }
+    }
+}
+
 fn update_system_tray_balance(app_handle: &tauri::AppHandle, balance: u32) -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("🎯 update_system_tray_balance called with balance: {}", balance);
 
@@ -1301,6 +2264,31 @@ fn update_system_tray_balance(app_handle: &tauri::AppHandle, balance: u32) -> Re
     Ok(())
 }
 
+fn show_connection_lost_in_tray(app_handle: &tauri::AppHandle, kind: &connectivity::FailureKind) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(tray) = app_handle.tray_by_id("main-tray") {
+        // Keep the last known balance in the title; the tooltip explains it may be stale
+        tray.set_tooltip(Some(&format!("Augment Credits - Offline ({}), balance may be outdated", kind.label())))?;
+    } else {
+        tracing::error!("❌ Could not find tray icon with ID 'main-tray'");
+        return Err("Tray icon not found".into());
+    }
+
+    Ok(())
+}
+
+fn show_session_expired_in_tray(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(tray) = app_handle.tray_by_id("main-tray") {
+        tray.set_title(Some("⚠"))?;
+        tray.set_tooltip(Some("Augment Credits - Session expired, log in again"))?;
+    } else {
+        tracing::error!("❌ Could not find tray icon with ID 'main-tray'");
+        return Err("Tray icon not found".into());
+    }
+
+    tracing::info!("✅ Tray shows expired session");
+    Ok(())
+}
+
 fn clear_system_tray(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("🗑️ clear_system_tray called - clearing tray display");
 
@@ -0,0 +1,231 @@
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::analytics::AlertLevel;
+use crate::augment_client::DailyGroupUsage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MixDimension {
+    Model,
+    Activity,
+}
+
+impl MixDimension {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MixDimension::Model => "Model",
+            MixDimension::Activity => "Activity",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MixChangeKind {
+    /// Not seen anywhere in the baseline window
+    NewEntry,
+    ShareIncrease,
+    ShareDecrease,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixChangeAlert {
+    pub dimension: MixDimension,
+    pub key: String,
+    pub kind: MixChangeKind,
+    pub date: NaiveDate,
+    /// Average share over the baseline window, in percent
+    pub baseline_share: f64,
+    /// Share on `date`, in percent
+    pub current_share: f64,
+    pub credits: i64,
+}
+
+impl MixChangeAlert {
+    /// Alert history / snooze id shared by every change in this dimension
+    pub fn rule_id(&self) -> String {
+        format!("mix_{}", self.dimension.label().to_lowercase())
+    }
+
+    pub fn level(&self) -> AlertLevel {
+        match self.kind {
+            MixChangeKind::NewEntry | MixChangeKind::ShareIncrease => AlertLevel::Warning,
+            MixChangeKind::ShareDecrease => AlertLevel::Info,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.kind {
+            MixChangeKind::NewEntry => format!("New {} in Use", self.dimension.label()),
+            MixChangeKind::ShareIncrease | MixChangeKind::ShareDecrease => {
+                format!("{} Mix Changed", self.dimension.label())
+            }
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self.kind {
+            MixChangeKind::NewEntry => format!(
+                "{} appeared on {} and used {} credits ({:.0}% of the day)",
+                self.key, self.date, self.credits, self.current_share
+            ),
+            MixChangeKind::ShareIncrease => format!(
+                "{} rose from {:.0}% to {:.0}% of usage on {} ({} credits)",
+                self.key, self.baseline_share, self.current_share, self.date, self.credits
+            ),
+            MixChangeKind::ShareDecrease => format!(
+                "{} fell from {:.0}% to {:.0}% of usage on {}",
+                self.key, self.baseline_share, self.current_share, self.date
+            ),
+        }
+    }
+}
+
+/// Per-day share (percent) of each group key
+fn daily_shares(usage: &[DailyGroupUsage]) -> BTreeMap<NaiveDate, HashMap<String, (i64, f64)>> {
+    let mut credits_by_day: BTreeMap<NaiveDate, HashMap<String, i64>> = BTreeMap::new();
+    for row in usage {
+        if let Ok(date) = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d") {
+            *credits_by_day.entry(date).or_default().entry(row.group_key.clone()).or_insert(0) += row.credits;
+        }
+    }
+
+    credits_by_day.into_iter()
+        .filter_map(|(date, groups)| {
+            let total: i64 = groups.values().sum();
+            if total <= 0 {
+                return None;
+            }
+            let shares = groups.into_iter()
+                .map(|(key, credits)| (key, (credits, credits as f64 / total as f64 * 100.0)))
+                .collect();
+            Some((date, shares))
+        })
+        .collect()
+}
+
+/// Compare the usage mix on `day` against the average mix of the preceding `baseline_days`.
+///
+/// Days with less than `min_daily_credits` in total are too small to judge and never alert.
+pub fn detect_mix_changes(
+    dimension: MixDimension,
+    usage: &[DailyGroupUsage],
+    day: NaiveDate,
+    baseline_days: u32,
+    share_shift_threshold: f64,
+    min_daily_credits: i64,
+) -> Vec<MixChangeAlert> {
+    let shares = daily_shares(usage);
+
+    let current = match shares.get(&day) {
+        Some(current) => current,
+        None => return Vec::new(),
+    };
+    let current_total: i64 = current.values().map(|(credits, _)| credits).sum();
+    if current_total < min_daily_credits {
+        return Vec::new();
+    }
+
+    let baseline_start = day - Duration::days(baseline_days as i64);
+    let baseline: Vec<&HashMap<String, (i64, f64)>> = shares
+        .range(baseline_start..day)
+        .map(|(_, groups)| groups)
+        .collect();
+    if baseline.is_empty() {
+        return Vec::new();
+    }
+
+    let mut keys: HashSet<&String> = current.keys().collect();
+    for groups in &baseline {
+        keys.extend(groups.keys());
+    }
+
+    let mut alerts = Vec::new();
+    for key in keys {
+        let baseline_share = baseline.iter()
+            .map(|groups| groups.get(key).map(|(_, share)| *share).unwrap_or(0.0))
+            .sum::<f64>() / baseline.len() as f64;
+        let seen_before = baseline.iter().any(|groups| groups.contains_key(key));
+        let (credits, current_share) = current.get(key).copied().unwrap_or((0, 0.0));
+
+        let kind = if !seen_before && credits > 0 {
+            Some(MixChangeKind::NewEntry)
+        } else if current_share - baseline_share >= share_shift_threshold {
+            Some(MixChangeKind::ShareIncrease)
+        } else if baseline_share - current_share >= share_shift_threshold {
+            Some(MixChangeKind::ShareDecrease)
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            alerts.push(MixChangeAlert {
+                dimension,
+                key: key.clone(),
+                kind,
+                date: day,
+                baseline_share,
+                current_share,
+                credits,
+            });
+        }
+    }
+
+    // Biggest movers first
+    alerts.sort_by(|a, b| {
+        let shift_a = (a.current_share - a.baseline_share).abs();
+        let shift_b = (b.current_share - b.baseline_share).abs();
+        shift_b.partial_cmp(&shift_a).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: &str, key: &str, credits: i64) -> DailyGroupUsage {
+        DailyGroupUsage { date: date.to_string(), group_key: key.to_string(), credits }
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    #[test]
+    fn test_detects_new_model_and_share_shift() {
+        let usage = vec![
+            row("2025-11-01", "sonnet", 90), row("2025-11-01", "haiku", 10),
+            row("2025-11-02", "sonnet", 80), row("2025-11-02", "haiku", 20),
+            row("2025-11-03", "sonnet", 20), row("2025-11-03", "haiku", 10), row("2025-11-03", "opus", 70),
+        ];
+
+        let alerts = detect_mix_changes(MixDimension::Model, &usage, day(3), 7, 25.0, 10);
+
+        let opus = alerts.iter().find(|a| a.key == "opus").unwrap();
+        assert_eq!(opus.kind, MixChangeKind::NewEntry);
+        assert!((opus.current_share - 70.0).abs() < 1e-9);
+
+        let sonnet = alerts.iter().find(|a| a.key == "sonnet").unwrap();
+        assert_eq!(sonnet.kind, MixChangeKind::ShareDecrease);
+        assert!((sonnet.baseline_share - 85.0).abs() < 1e-9);
+
+        assert!(alerts.iter().all(|a| a.key != "haiku"));
+        assert_eq!(opus.level(), AlertLevel::Warning);
+        assert_eq!(sonnet.level(), AlertLevel::Info);
+        assert_eq!(opus.rule_id(), "mix_model");
+    }
+
+    #[test]
+    fn test_stable_mix_and_small_days_do_not_alert() {
+        let usage = vec![
+            row("2025-11-01", "chat", 50), row("2025-11-01", "agent", 50),
+            row("2025-11-02", "chat", 55), row("2025-11-02", "agent", 45),
+            row("2025-11-03", "agent", 5),
+        ];
+
+        assert!(detect_mix_changes(MixDimension::Activity, &usage, day(2), 7, 20.0, 10).is_empty());
+        assert!(detect_mix_changes(MixDimension::Activity, &usage, day(3), 7, 20.0, 10).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::error::{AppError, AppResult};
+
+/// How outbound requests find their way out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// Use HTTP_PROXY / HTTPS_PROXY / NO_PROXY from the environment, if set
+    #[default]
+    System,
+    /// Never use a proxy
+    Direct,
+    /// Use the proxy configured below
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// e.g. http://proxy.corp:3128, https://proxy.corp:443 or socks5://proxy.corp:1080
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Hosts, domains (".corp.example") or CIDR ranges that bypass the proxy
+    pub no_proxy: Vec<String>,
+}
+
+/// Proxy and trust settings shared by every outbound HTTP client
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub proxy: ProxySettings,
+    /// PEM files (single certificates or bundles) trusted in addition to the system roots
+    pub ca_certificates: Vec<PathBuf>,
+}
+
+impl NetworkSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.proxy.mode == ProxyMode::Manual {
+            let url = self.proxy.url.as_deref().unwrap_or_default();
+            let parsed = url::Url::parse(url).map_err(|e| {
+                AppError::Config(config::ConfigError::Message(format!("Invalid proxy URL '{}': {}", url, e)))
+            })?;
+            if !["http", "https", "socks5", "socks5h"].contains(&parsed.scheme()) {
+                return Err(AppError::Config(config::ConfigError::Message(format!(
+                    "Unsupported proxy scheme '{}' (use http, https, socks5 or socks5h)",
+                    parsed.scheme()
+                ))));
+            }
+        }
+        if self.proxy.password.is_some() && self.proxy.username.is_none() {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Proxy password given without a username".to_string())
+            ));
+        }
+        Ok(())
+    }
+
+    /// Client builder with the proxy and extra CA certificates applied
+    pub fn client_builder(&self) -> AppResult<ClientBuilder> {
+        self.apply(Client::builder())
+    }
+
+    pub fn apply(&self, mut builder: ClientBuilder) -> AppResult<ClientBuilder> {
+        match self.proxy.mode {
+            ProxyMode::System => {}
+            ProxyMode::Direct => builder = builder.no_proxy(),
+            ProxyMode::Manual => builder = builder.proxy(self.manual_proxy()?),
+        }
+
+        for certificate in self.load_certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(builder)
+    }
+
+    fn manual_proxy(&self) -> AppResult<Proxy> {
+        let url = self.proxy.url.as_deref().ok_or_else(|| {
+            AppError::Config(config::ConfigError::Message("Manual proxy mode needs a proxy URL".to_string()))
+        })?;
+
+        let mut proxy = Proxy::all(url)?;
+        if let Some(username) = &self.proxy.username {
+            proxy = proxy.basic_auth(username, self.proxy.password.as_deref().unwrap_or_default());
+        }
+        if !self.proxy.no_proxy.is_empty() {
+            proxy = proxy.no_proxy(NoProxy::from_string(&self.proxy.no_proxy.join(",")));
+        }
+        Ok(proxy)
+    }
+
+    fn load_certificates(&self) -> AppResult<Vec<Certificate>> {
+        let mut certificates = Vec::new();
+        for path in &self.ca_certificates {
+            let pem = std::fs::read(path).map_err(|e| {
+                AppError::Config(config::ConfigError::Message(format!(
+                    "Can't read CA certificate {}: {}", path.display(), e
+                )))
+            })?;
+            let bundle = Certificate::from_pem_bundle(&pem).map_err(|e| {
+                AppError::Config(config::ConfigError::Message(format!(
+                    "Invalid CA certificate {}: {}", path.display(), e
+                )))
+            })?;
+            if bundle.is_empty() {
+                return Err(AppError::Config(config::ConfigError::Message(format!(
+                    "No certificates found in {}", path.display()
+                ))));
+            }
+            tracing::info!("🔐 Trusting {} extra CA certificate(s) from {}", bundle.len(), path.display());
+            certificates.extend(bundle);
+        }
+        Ok(certificates)
+    }
+
+    /// Chrome command-line switches for the scraper's headless browser. Chrome can't take proxy
+    /// credentials or CA files on the command line; those need the system settings.
+    pub fn browser_args(&self) -> Vec<String> {
+        match self.proxy.mode {
+            ProxyMode::System => Vec::new(),
+            ProxyMode::Direct => vec!["--no-proxy-server".to_string()],
+            ProxyMode::Manual => {
+                let mut args = Vec::new();
+                if let Some(url) = &self.proxy.url {
+                    args.push(format!("--proxy-server={}", url));
+                }
+                if !self.proxy.no_proxy.is_empty() {
+                    args.push(format!("--proxy-bypass-list={}", self.proxy.no_proxy.join(";")));
+                }
+                args
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(url: &str) -> NetworkSettings {
+        NetworkSettings {
+            proxy: ProxySettings {
+                mode: ProxyMode::Manual,
+                url: Some(url.to_string()),
+                no_proxy: vec!["localhost".to_string(), ".corp.example".to_string()],
+                ..ProxySettings::default()
+            },
+            ..NetworkSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_proxy_settings() {
+        assert!(NetworkSettings::default().validate().is_ok());
+        assert!(manual("http://proxy.corp:3128").validate().is_ok());
+        assert!(manual("socks5h://proxy.corp:1080").validate().is_ok());
+        assert!(manual("ftp://proxy.corp").validate().is_err());
+        assert!(manual("not a url").validate().is_err());
+
+        let mut password_only = manual("http://proxy.corp:3128");
+        password_only.proxy.password = Some("secret".to_string());
+        assert!(password_only.validate().is_err());
+    }
+
+    #[test]
+    fn test_builds_clients() {
+        let mut settings = manual("http://proxy.corp:3128");
+        settings.proxy.username = Some("me".to_string());
+        settings.proxy.password = Some("secret".to_string());
+        assert!(settings.client_builder().unwrap().build().is_ok());
+
+        assert_eq!(
+            settings.browser_args(),
+            vec!["--proxy-server=http://proxy.corp:3128", "--proxy-bypass-list=localhost;.corp.example"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_missing_or_empty_ca_files() {
+        let missing = NetworkSettings {
+            ca_certificates: vec![PathBuf::from("/nonexistent/corp-root.pem")],
+            ..NetworkSettings::default()
+        };
+        assert!(missing.client_builder().is_err());
+
+        let path = std::env::temp_dir().join(format!("empty-ca-{}.pem", std::process::id()));
+        std::fs::write(&path, "not a certificate").unwrap();
+        let empty = NetworkSettings { ca_certificates: vec![path.clone()], ..NetworkSettings::default() };
+        assert!(empty.client_builder().is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,418 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use notify_rust::{Notification, Timeout};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
+use crate::alert_rules::NotificationChannel;
+use crate::alert_state::{AlertTransition, TransitionKind};
+use crate::analytics::AlertLevel;
+use crate::chat_channels::{self, ChatAlert, ChatChannelConfig};
+use crate::email::{self, EmailSender, EmailSettings};
+use crate::error::{AppError, AppResult};
+use crate::webhook::{WebhookAlert, WebhookConfig, WebhookSender};
+
+/// A notification on its way to the sinks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutgoingNotification {
+    pub title: String,
+    pub message: String,
+    pub level: AlertLevel,
+    /// Freedesktop sound name for the notification server to play, if any
+    pub sound_hint: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Rule transition behind the notification, for channels that report rule details
+    pub transition: Option<AlertTransition>,
+    pub balance: Option<u32>,
+    pub hours_to_depletion: Option<f64>,
+    pub portal_url: Option<String>,
+}
+
+impl OutgoingNotification {
+    pub fn new(title: &str, message: &str, level: AlertLevel) -> Self {
+        Self {
+            title: title.to_string(),
+            message: message.to_string(),
+            level,
+            sound_hint: None,
+            timestamp: Utc::now(),
+            transition: None,
+            balance: None,
+            hours_to_depletion: None,
+            portal_url: None,
+        }
+    }
+
+    /// The notification in the shape chat messages and alert emails are rendered from
+    pub fn chat_alert(&self) -> ChatAlert {
+        ChatAlert {
+            title: self.title.clone(),
+            level: self.level,
+            message: self.message.clone(),
+            balance: self.balance,
+            hours_to_depletion: self.hours_to_depletion,
+            portal_url: self.portal_url.clone(),
+        }
+    }
+
+    pub fn for_transition(title: &str, transition: &AlertTransition, balance: Option<u32>) -> Self {
+        Self {
+            transition: Some(transition.clone()),
+            balance,
+            ..Self::new(title, &transition.message, transition.severity)
+        }
+    }
+}
+
+/// Somewhere notifications can be delivered to
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// Stable name used in delivery outcomes, e.g. "desktop" or "webhook:ops"
+    fn name(&self) -> String;
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()>;
+}
+
+/// Result of handing one notification to one sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryOutcome {
+    pub sink: String,
+    pub title: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl DeliveryOutcome {
+    pub fn new(sink: &str, title: &str, result: &AppResult<()>) -> Self {
+        Self {
+            sink: sink.to_string(),
+            title: title.to_string(),
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Delivery outcomes kept for the UI
+const DELIVERY_LOG_SIZE: usize = 200;
+
+/// Runs deliveries on background tasks, so retries and slow servers never hold up the caller
+/// (or the notification manager lock), and keeps the outcome of each finished delivery
+#[derive(Clone, Default)]
+pub struct DeliveryQueue {
+    log: Arc<Mutex<VecDeque<DeliveryOutcome>>>,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl DeliveryQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand `notification` to `sink` in the background
+    pub fn push(&self, sink: Arc<dyn NotificationSink>, notification: OutgoingNotification) {
+        let log = self.clone();
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget finished deliveries so the set doesn't grow for the lifetime of the app
+        while tasks.try_join_next().is_some() {}
+
+        tasks.spawn(async move {
+            let result = sink.deliver(&notification).await;
+            match &result {
+                Ok(()) => tracing::info!("📤 {} delivered: {}", sink.name(), notification.title),
+                Err(e) => tracing::warn!("⚠️ Notification sink '{}' failed: {}", sink.name(), e),
+            }
+            log.record(DeliveryOutcome::new(&sink.name(), &notification.title, &result));
+        });
+    }
+
+    pub fn record(&self, outcome: DeliveryOutcome) {
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        if log.len() >= DELIVERY_LOG_SIZE {
+            log.pop_front();
+        }
+        log.push_back(outcome);
+    }
+
+    /// Most recent delivery outcomes, newest first
+    pub fn outcomes(&self) -> Vec<DeliveryOutcome> {
+        self.log.lock()
+            .map(|log| log.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Wait until everything queued so far has been delivered (or has failed)
+    pub async fn wait_idle(&self) {
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// Which sinks receive desktop-style notifications, and where notifications that don't come
+/// from an alert rule go
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSinkSettings {
+    pub desktop: bool,
+    /// Write every notification to the application log
+    pub log: bool,
+    /// Webhooks and chat channels that also receive connection, usage mix and subscription
+    /// notifications. Alert rules pick their own channels.
+    pub channels: Vec<NotificationChannel>,
+}
+
+impl Default for NotificationSinkSettings {
+    fn default() -> Self {
+        Self {
+            desktop: true,
+            log: false,
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl NotificationSinkSettings {
+    pub fn validate(&self, webhooks: &[WebhookConfig], chat_channels: &[ChatChannelConfig]) -> AppResult<()> {
+        for channel in &self.channels {
+            let unknown = match channel {
+                NotificationChannel::Desktop => None,
+                NotificationChannel::Webhook { id } => (!webhooks.iter().any(|w| &w.id == id))
+                    .then(|| format!("webhook '{}'", id)),
+                NotificationChannel::Chat { id } => (!chat_channels.iter().any(|c| &c.id == id))
+                    .then(|| format!("chat channel '{}'", id)),
+            };
+            if let Some(unknown) = unknown {
+                return Err(AppError::Config(
+                    config::ConfigError::Message(format!("Notification sinks refer to unknown {}", unknown))
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sinks behind `NotificationChannel::Desktop`
+    pub fn build(&self) -> Vec<Box<dyn NotificationSink>> {
+        let mut sinks: Vec<Box<dyn NotificationSink>> = Vec::new();
+
+        if self.desktop {
+            sinks.push(Box::new(DesktopSink));
+        }
+        if self.log {
+            sinks.push(Box::new(LogSink));
+        }
+
+        sinks
+    }
+}
+
+/// Native desktop notification
+pub struct DesktopSink;
+
+#[async_trait]
+impl NotificationSink for DesktopSink {
+    fn name(&self) -> String {
+        "desktop".to_string()
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        let mut desktop = Notification::new();
+        desktop
+            .summary(&notification.title)
+            .body(&notification.message)
+            .appname("orb Credit Monitor")
+            .timeout(Timeout::Milliseconds(5000));
+
+        // Set icon based on alert level
+        match notification.level {
+            AlertLevel::Critical => {
+                desktop.icon("dialog-error");
+            }
+            AlertLevel::Warning => {
+                desktop.icon("dialog-warning");
+            }
+            AlertLevel::Info => {
+                desktop.icon("dialog-information");
+            }
+        }
+
+        if let Some(sound) = &notification.sound_hint {
+            desktop.sound_name(sound);
+        }
+
+        desktop.show()
+            .map_err(|e| AppError::Notification(format!("Failed to show notification: {}", e)))?;
+
+        tracing::info!("Sent notification: {} - {}", notification.title, notification.message);
+        Ok(())
+    }
+}
+
+/// Posts notifications to a configured webhook
+pub struct WebhookSink {
+    webhook: WebhookConfig,
+    sender: WebhookSender,
+}
+
+impl WebhookSink {
+    pub fn new(webhook: WebhookConfig, sender: WebhookSender) -> Self {
+        Self { webhook, sender }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook:{}", self.webhook.id)
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        let alert = match &notification.transition {
+            Some(transition) => WebhookAlert::from_transition(transition, notification.balance),
+            None => WebhookAlert {
+                rule_id: "notification".to_string(),
+                rule_name: notification.title.clone(),
+                event: TransitionKind::Fired,
+                severity: notification.level,
+                message: notification.message.clone(),
+                balance: notification.balance,
+                value: None,
+                threshold: 0.0,
+                timestamp: notification.timestamp,
+            },
+        };
+
+        let delivery = self.sender.send(&self.webhook, &alert).await;
+        if delivery.success {
+            Ok(())
+        } else {
+            Err(AppError::Notification(format!(
+                "Webhook '{}' failed: {}",
+                self.webhook.name,
+                delivery.error.unwrap_or_else(|| "unknown error".to_string())
+            )))
+        }
+    }
+}
+
+/// Posts notifications to a Slack, Discord or Teams incoming webhook in the platform's format
+pub struct ChatSink {
+    channel: ChatChannelConfig,
+    sender: WebhookSender,
+}
+
+impl ChatSink {
+    pub fn new(channel: ChatChannelConfig, sender: WebhookSender) -> Self {
+        Self { channel, sender }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for ChatSink {
+    fn name(&self) -> String {
+        format!("chat:{}", self.channel.id)
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        let body = chat_channels::format_message(self.channel.platform, &notification.chat_alert()).to_string();
+
+        let delivery = self.sender.deliver(&self.channel.as_webhook(), body).await;
+        if delivery.success {
+            Ok(())
+        } else {
+            Err(AppError::Notification(format!(
+                "{} channel '{}' failed: {}",
+                self.channel.platform.label(),
+                self.channel.name,
+                delivery.error.unwrap_or_else(|| "unknown error".to_string())
+            )))
+        }
+    }
+}
+
+/// Emails notifications to the configured recipients
+pub struct EmailSink {
+    sender: EmailSender,
+}
+
+impl EmailSink {
+    pub fn new(settings: &EmailSettings) -> Self {
+        Self { sender: EmailSender::new(settings) }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for EmailSink {
+    fn name(&self) -> String {
+        "email".to_string()
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        let (subject, body) = email::render_alert(&notification.chat_alert());
+        self.sender.send(&subject, &body).await
+    }
+}
+
+/// Writes notifications to the application log
+pub struct LogSink;
+
+#[async_trait]
+impl NotificationSink for LogSink {
+    fn name(&self) -> String {
+        "log".to_string()
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        match notification.level {
+            AlertLevel::Critical => tracing::error!("🔔 {}: {}", notification.title, notification.message),
+            AlertLevel::Warning => tracing::warn!("🔔 {}: {}", notification.title, notification.message),
+            AlertLevel::Info => tracing::info!("🔔 {}: {}", notification.title, notification.message),
+        }
+        Ok(())
+    }
+}
+
+/// Keeps delivered notifications in memory; clones share the same buffer, so a test can keep
+/// one handle and give the other to the manager
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    delivered: Arc<Mutex<Vec<OutgoingNotification>>>,
+    fail_with: Option<String>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A sink that rejects every notification with `error`
+    pub fn failing(error: &str) -> Self {
+        Self {
+            delivered: Arc::default(),
+            fail_with: Some(error.to_string()),
+        }
+    }
+
+    pub fn delivered(&self) -> Vec<OutgoingNotification> {
+        self.delivered.lock().map(|d| d.clone()).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl NotificationSink for MemorySink {
+    fn name(&self) -> String {
+        "memory".to_string()
+    }
+
+    async fn deliver(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        if let Some(error) = &self.fail_with {
+            return Err(AppError::Notification(error.clone()));
+        }
+        if let Ok(mut delivered) = self.delivered.lock() {
+            delivered.push(notification.clone());
+        }
+        Ok(())
+    }
+}
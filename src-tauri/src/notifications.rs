@@ -1,75 +1,370 @@
-use notify_rust::{Notification, Timeout};
 use std::time::{Duration, Instant};
-use std::collections::HashMap;
-use crate::analytics::{AlertLevel, UsageAnalytics};
-use crate::error::{AppError, AppResult};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::alert_rules::{self, AlertRule, NotificationChannel, RuleEvaluation};
+use crate::alert_state::{AlertTransition, TransitionKind};
+use crate::analytics::AlertLevel;
+use crate::connectivity::{self, ConnectivityChange};
+use crate::chat_channels::{self, ChatAlert, ChatChannelConfig};
+use crate::email::EmailSettings;
+use crate::error::AppResult;
+use crate::mix_alerts::MixChangeAlert;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::notification_sinks::{ChatSink, DeliveryOutcome, DeliveryQueue, DesktopSink, EmailSink, NotificationSink, OutgoingNotification, WebhookSink};
+use crate::quiet_hours::{self, DeferredNotification, QuietHoursSettings};
+use crate::subscription_watch::SubscriptionEvent;
+use crate::sounds::{self, SoundPlayer, SoundSettings};
+use crate::network::NetworkSettings;
+use crate::webhook::{WebhookAlert, WebhookConfig, WebhookDelivery, WebhookSender};
+
+/// One round of rule evaluation, ready to be delivered
+#[derive(Clone)]
+pub struct AlertBatch<'a> {
+    pub rules: &'a [AlertRule],
+    pub evaluations: &'a [RuleEvaluation],
+    pub transitions: &'a [AlertTransition],
+    /// Rules whose notifications are snoozed right now
+    pub snoozed_rule_ids: &'a HashSet<String>,
+    pub balance: Option<u32>,
+    pub hours_to_depletion: Option<f64>,
+    pub portal_url: Option<String>,
+}
 
 pub struct NotificationManager {
     last_notifications: HashMap<String, Instant>,
     notification_cooldown: Duration,
+    webhook_sender: WebhookSender,
+    quiet_hours: QuietHoursSettings,
+    deferred: Vec<DeferredNotification>,
+    sound_player: SoundPlayer,
+    sound_enabled: bool,
+    sound_settings: SoundSettings,
+    /// Sinks behind `NotificationChannel::Desktop`
+    sinks: Vec<Box<dyn NotificationSink>>,
+    webhooks: Vec<WebhookConfig>,
+    chat_channels: Vec<ChatChannelConfig>,
+    email: EmailSettings,
+    /// Channels, besides the desktop, for notifications that don't come from an alert rule
+    broadcast_channels: Vec<NotificationChannel>,
+    deliveries: DeliveryQueue,
+    /// Where the deferred queue is kept across restarts
+    database: Option<Arc<Database>>,
 }
 
+/// `app_metadata` key holding notifications deferred by quiet hours
+const DEFERRED_KEY: &str = "deferred_notifications";
+
 impl NotificationManager {
     pub fn new() -> Self {
         Self {
             last_notifications: HashMap::new(),
             notification_cooldown: Duration::from_secs(300), // 5 minutes cooldown
+            webhook_sender: WebhookSender::new(),
+            quiet_hours: QuietHoursSettings::default(),
+            deferred: Vec::new(),
+            sound_player: SoundPlayer::new(),
+            sound_enabled: false,
+            sound_settings: SoundSettings::default(),
+            sinks: vec![Box::new(DesktopSink)],
+            webhooks: Vec::new(),
+            chat_channels: Vec::new(),
+            email: EmailSettings::default(),
+            broadcast_channels: Vec::new(),
+            deliveries: DeliveryQueue::new(),
+            database: None,
         }
     }
-    
-    pub async fn check_and_send_alerts(&mut self, analytics: &UsageAnalytics, current_balance: u32) {
-        // Check balance thresholds
-        if current_balance <= 100 {
-            self.send_notification_if_needed(
-                "critical_balance",
-                "Critical Balance Alert",
-                &format!("Only {} credits remaining!", current_balance),
-                AlertLevel::Critical,
-            ).await;
-        } else if current_balance <= 500 {
-            self.send_notification_if_needed(
-                "low_balance",
-                "Low Balance Warning",
-                &format!("{} credits remaining", current_balance),
-                AlertLevel::Warning,
-            ).await;
+
+    /// Keep the quiet hours queue in `database`, restoring whatever was deferred before a restart
+    pub async fn attach_database(&mut self, database: Arc<Database>) {
+        match database.get_metadata(DEFERRED_KEY).await {
+            Ok(Some(json)) => match serde_json::from_str::<Vec<DeferredNotification>>(&json) {
+                Ok(deferred) => {
+                    if !deferred.is_empty() {
+                        tracing::info!("🌙 Restored {} notification(s) deferred by quiet hours", deferred.len());
+                    }
+                    self.deferred = deferred;
+                }
+                Err(e) => tracing::warn!("⚠️ Ignoring unreadable deferred notifications: {}", e),
+            },
+            Ok(None) => {}
+            Err(e) => tracing::warn!("⚠️ Failed to load deferred notifications: {}", e),
         }
-        
-        // Check time-based alerts
-        if let Some(hours_remaining) = analytics.estimated_hours_remaining {
-            if hours_remaining <= 2.0 {
-                self.send_notification_if_needed(
-                    "time_critical",
-                    "Credits Depleting Soon",
-                    &format!("Credits will run out in {:.1} hours at current usage rate", hours_remaining),
-                    AlertLevel::Critical,
-                ).await;
-            } else if hours_remaining <= 24.0 {
-                self.send_notification_if_needed(
-                    "time_warning",
-                    "Credits Running Low",
-                    &format!("Credits will run out in {:.1} hours at current usage rate", hours_remaining),
-                    AlertLevel::Warning,
-                ).await;
+        self.database = Some(database);
+    }
+
+    async fn save_deferred(&self) {
+        let Some(database) = &self.database else { return };
+        let result = match serde_json::to_string(&self.deferred) {
+            Ok(json) => database.set_metadata(DEFERRED_KEY, &json).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("⚠️ Failed to save deferred notifications: {}", e);
+        }
+    }
+
+    /// Apply the notification-related parts of `config`
+    pub fn configure(&mut self, config: &AppConfig) {
+        self.set_quiet_hours(config.quiet_hours.clone());
+        self.set_sound_settings(config.enable_sound_alerts, config.sounds.clone());
+        self.set_network(&config.network);
+        self.set_sinks(config.notification_sinks.build());
+        self.set_channels(
+            config.webhooks.clone(),
+            config.chat_channels.clone(),
+            config.email.clone(),
+            config.notification_sinks.channels.clone(),
+        );
+    }
+
+    /// Route webhook and chat deliveries through the configured proxy / CA certificates
+    pub fn set_network(&mut self, network: &NetworkSettings) {
+        self.webhook_sender = WebhookSender::with_network(network);
+    }
+
+    /// Replace the sinks desktop-style notifications fan out to
+    pub fn set_sinks(&mut self, sinks: Vec<Box<dyn NotificationSink>>) {
+        self.sinks = sinks;
+    }
+
+    /// Webhooks, chat channels and email that `NotificationChannel`s resolve to
+    pub fn set_channels(
+        &mut self,
+        webhooks: Vec<WebhookConfig>,
+        chat_channels: Vec<ChatChannelConfig>,
+        email: EmailSettings,
+        broadcast_channels: Vec<NotificationChannel>,
+    ) {
+        self.webhooks = webhooks;
+        self.chat_channels = chat_channels;
+        self.email = email;
+        self.broadcast_channels = broadcast_channels;
+    }
+
+    /// Where notifications that don't come from an alert rule go
+    fn broadcast_route(&self) -> Vec<NotificationChannel> {
+        let mut channels = vec![NotificationChannel::Desktop];
+        channels.extend(self.broadcast_channels.iter().cloned());
+        channels
+    }
+
+    /// Webhook, chat and email sinks for `channels`, each at most once. Unless `channels` names a
+    /// chat channel, the chat channels subscribed to `level` are included; so is email when it
+    /// is subscribed to `level`.
+    fn remote_sinks(&self, channels: &[NotificationChannel], level: AlertLevel) -> Vec<Arc<dyn NotificationSink>> {
+        let mut sinks: Vec<Arc<dyn NotificationSink>> = Vec::new();
+
+        for channel in channels {
+            match channel {
+                NotificationChannel::Desktop => {}
+                NotificationChannel::Webhook { id } => match self.webhooks.iter().find(|w| &w.id == id && w.enabled) {
+                    Some(webhook) => sinks.push(Arc::new(WebhookSink::new(webhook.clone(), self.webhook_sender.clone()))),
+                    None => tracing::warn!("⚠️ Webhook '{}' is missing or disabled", id),
+                },
+                NotificationChannel::Chat { id } => match self.chat_channels.iter().find(|c| &c.id == id && c.enabled) {
+                    Some(chat) => sinks.push(Arc::new(ChatSink::new(chat.clone(), self.webhook_sender.clone()))),
+                    None => tracing::warn!("⚠️ Chat channel '{}' is missing or disabled", id),
+                },
             }
         }
-        
-        // Check for unusual usage patterns
-        if analytics.usage_rate_per_hour > 0.0 {
-            let recent_rate = analytics.usage_rate_per_hour;
-            let historical_average = analytics.average_session_usage;
-            
-            if recent_rate > historical_average * 2.0 {
-                self.send_notification_if_needed(
-                    "high_usage",
-                    "High Usage Detected",
-                    &format!("Current usage rate ({:.1}/hour) is significantly higher than average", recent_rate),
-                    AlertLevel::Warning,
-                ).await;
+
+        if !channels.iter().any(|c| matches!(c, NotificationChannel::Chat { .. })) {
+            for chat in self.chat_channels.iter().filter(|c| c.accepts(level)) {
+                sinks.push(Arc::new(ChatSink::new(chat.clone(), self.webhook_sender.clone())));
             }
         }
+        if self.email.accepts(level) {
+            sinks.push(Arc::new(EmailSink::new(&self.email)));
+        }
+
+        let mut seen = HashSet::new();
+        sinks.retain(|sink| seen.insert(sink.name()));
+        sinks
+    }
+
+    /// Send `notification` to `channels`, or hold it for all of them until quiet hours end
+    async fn deliver(&mut self, notification: OutgoingNotification, channels: &[NotificationChannel]) -> AppResult<()> {
+        let now = chrono::Utc::now();
+        if self.quiet_hours.should_defer(notification.level, now) {
+            tracing::info!("🌙 Quiet hours, deferring notification: {}", notification.title);
+            self.deferred.push(DeferredNotification {
+                title: notification.title,
+                message: notification.message,
+                level: notification.level,
+                raised_at: now,
+                channels: channels.to_vec(),
+            });
+            self.save_deferred().await;
+            return Ok(());
+        }
+
+        self.deliver_now(notification, channels).await
+    }
+
+    /// Queue `notification` for its remote channels, then show it on the desktop if routed there
+    async fn deliver_now(&self, notification: OutgoingNotification, channels: &[NotificationChannel]) -> AppResult<()> {
+        for sink in self.remote_sinks(channels, notification.level) {
+            self.deliveries.push(sink, notification.clone());
+        }
+
+        if channels.contains(&NotificationChannel::Desktop) {
+            self.show_with_sound(notification).await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Most recent delivery outcomes, newest first
+    pub fn delivery_outcomes(&self) -> Vec<DeliveryOutcome> {
+        self.deliveries.outcomes()
+    }
+
+    fn record_outcome(&self, outcome: DeliveryOutcome) {
+        self.deliveries.record(outcome);
+    }
+
+    /// Hand `notification` to every sink. Fails only when no sink accepted it.
+    async fn dispatch(&self, notification: &OutgoingNotification) -> AppResult<()> {
+        let mut last_error = None;
+        let mut delivered = self.sinks.is_empty();
+
+        for sink in &self.sinks {
+            let result = sink.deliver(notification).await;
+            self.record_outcome(DeliveryOutcome::new(&sink.name(), &notification.title, &result));
+            match result {
+                Ok(()) => delivered = true,
+                Err(e) => {
+                    tracing::warn!("⚠️ Notification sink '{}' failed: {}", sink.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !delivered => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_sound_settings(&mut self, enabled: bool, settings: SoundSettings) {
+        self.sound_enabled = enabled;
+        self.sound_settings = settings;
+        self.sound_player.reset_availability();
+    }
+
+    pub fn set_quiet_hours(&mut self, settings: QuietHoursSettings) {
+        self.quiet_hours = settings;
+    }
+
+    /// Show a notification and play the alert sound for `level` when sound alerts are enabled.
+    /// The sound plays in the background so the caller's lock isn't held while players are tried.
+    async fn show_with_sound(&self, mut notification: OutgoingNotification) -> AppResult<()> {
+        let level = notification.level;
+        let mut sound_hint = None;
+
+        if self.sound_enabled {
+            // Once no player worked, Linux notification servers play a themed sound themselves
+            if self.sound_player.is_unavailable() && cfg!(all(unix, not(target_os = "macos"))) {
+                sound_hint = Some(sounds::sound_theme_name(level));
+            } else {
+                self.sound_player.play_in_background(level, self.sound_settings.clone());
+            }
+        }
+
+        notification.sound_hint = sound_hint.map(str::to_string);
+        self.dispatch(&notification).await
+    }
+
+    /// Once quiet hours are over, deliver everything held back as one summary notification,
+    /// to every channel the deferred notifications were routed to
+    pub async fn flush_deferred(&mut self) {
+        if self.deferred.is_empty() || self.quiet_hours.active_until(chrono::Utc::now()).is_some() {
+            return;
+        }
+
+        if let Some((title, message, level)) = quiet_hours::summarize(&self.deferred) {
+            let route = quiet_hours::combined_route(&self.deferred);
+            match self.deliver_now(OutgoingNotification::new(&title, &message, level), &route).await {
+                Ok(()) => {
+                    self.deferred.clear();
+                    self.save_deferred().await;
+                }
+                Err(e) => tracing::error!("Failed to send quiet hours summary: {}", e),
+            }
+        }
+    }
+
+    pub fn deferred_notifications(&self) -> &[DeferredNotification] {
+        &self.deferred
     }
     
+    /// Deliver fired and escalated alerts to their channels. The state machine in `alert_state`
+    /// already decides when an alert is new, so no cooldown applies here.
+    pub async fn process_alert_transitions(&mut self, batch: &AlertBatch<'_>, notifications_enabled: bool) {
+        let AlertBatch { rules, evaluations, transitions, snoozed_rule_ids, balance, .. } = *batch;
+
+        for transition in transitions.iter().filter(|t| t.kind == TransitionKind::Resolved) {
+            tracing::info!("✅ Alert resolved: {}", transition.rule_name);
+        }
+
+        if !notifications_enabled {
+            return;
+        }
+
+        // When low and critical fire together, only the most severe one is worth a notification
+        let notify_ids: HashSet<&str> = alert_rules::select_notifications(rules, evaluations)
+            .into_iter()
+            .map(|evaluation| evaluation.rule_id.as_str())
+            .collect();
+
+        for transition in transitions {
+            if transition.kind == TransitionKind::Resolved || !notify_ids.contains(transition.rule_id.as_str()) {
+                continue;
+            }
+            if snoozed_rule_ids.contains(&transition.rule_id) {
+                tracing::info!("💤 Alert snoozed, not notifying: {}", transition.rule_name);
+                continue;
+            }
+
+            let title = match transition.kind {
+                TransitionKind::Escalated => format!("{} (worsening)", transition.rule_name),
+                _ => transition.rule_name.clone(),
+            };
+
+            let mut notification = OutgoingNotification::for_transition(&title, transition, balance);
+            notification.hours_to_depletion = batch.hours_to_depletion;
+            notification.portal_url = batch.portal_url.clone();
+
+            if let Err(e) = self.deliver(notification, &transition.channels).await {
+                tracing::error!("Failed to send notification: {}", e);
+            }
+        }
+    }
+
+    pub async fn send_chat_message(&self, channel: &ChatChannelConfig, alert: &ChatAlert) -> WebhookDelivery {
+        let body = chat_channels::format_message(channel.platform, alert).to_string();
+        self.webhook_sender.deliver(&channel.as_webhook(), body).await
+    }
+    
+    pub async fn send_mix_change_alerts(&mut self, alerts: &[MixChangeAlert]) {
+        for alert in alerts {
+            let notification_id = format!("mix_{:?}_{}_{}", alert.dimension, alert.key, alert.date);
+            self.send_notification_if_needed(&notification_id, &alert.title(), &alert.message(), alert.level()).await;
+        }
+    }
+
+    pub async fn send_subscription_events(&mut self, events: &[SubscriptionEvent]) {
+        let route = self.broadcast_route();
+        for event in events {
+            let notification = OutgoingNotification::new(&event.title, &event.message, event.level);
+            if let Err(e) = self.deliver(notification, &route).await {
+                tracing::error!("Failed to send subscription notification: {}", e);
+            }
+        }
+    }
+
     async fn send_notification_if_needed(
         &mut self,
         notification_id: &str,
@@ -84,39 +379,21 @@ impl NotificationManager {
             }
         }
         
-        if let Err(e) = self.send_notification(title, message, level).await {
+        let route = self.broadcast_route();
+        if let Err(e) = self.deliver(OutgoingNotification::new(title, message, level), &route).await {
             tracing::error!("Failed to send notification: {}", e);
         } else {
             self.last_notifications.insert(notification_id.to_string(), Instant::now());
         }
     }
     
+    /// Deliver right away, even during quiet hours; fails only when no desktop sink accepted it
     pub async fn send_notification(&self, title: &str, message: &str, level: AlertLevel) -> AppResult<()> {
-        let mut notification = Notification::new();
-        notification
-            .summary(title)
-            .body(message)
-            .appname("orb Credit Monitor")
-            .timeout(Timeout::Milliseconds(5000));
-        
-        // Set icon based on alert level
-        match level {
-            AlertLevel::Critical => {
-                notification.icon("dialog-error");
-            }
-            AlertLevel::Warning => {
-                notification.icon("dialog-warning");
-            }
-            AlertLevel::Info => {
-                notification.icon("dialog-information");
-            }
+        let notification = OutgoingNotification::new(title, message, level);
+        for sink in self.remote_sinks(&self.broadcast_channels, level) {
+            self.deliveries.push(sink, notification.clone());
         }
-        
-        notification.show()
-            .map_err(|e| AppError::Notification(format!("Failed to show notification: {}", e)))?;
-        
-        tracing::info!("Sent notification: {} - {}", title, message);
-        Ok(())
+        self.dispatch(&notification).await
     }
     
     pub async fn send_balance_update(&self, current_balance: u32, previous_balance: Option<u32>) -> AppResult<()> {
@@ -148,14 +425,56 @@ impl NotificationManager {
         ).await
     }
     
-    pub async fn send_connection_status(&self, is_connected: bool) -> AppResult<()> {
-        let (title, message, level) = if is_connected {
-            ("Connection Restored", "Successfully reconnected to orb portal", AlertLevel::Info)
-        } else {
-            ("Connection Lost", "Unable to connect to orb portal", AlertLevel::Warning)
+    /// Tell the user that `provider` became unreachable, or reachable again
+    pub async fn send_connection_status(&mut self, provider: &str, change: &ConnectivityChange) -> AppResult<()> {
+        let (title, message, level) = match change {
+            ConnectivityChange::Lost { since, kind } => (
+                "Connection Lost",
+                format!(
+                    "Unable to reach {} since {} ({})",
+                    provider,
+                    since.with_timezone(&chrono::Local).format("%H:%M"),
+                    kind.label()
+                ),
+                AlertLevel::Warning,
+            ),
+            ConnectivityChange::Restored { outage } => (
+                "Connection Restored",
+                format!("Reconnected to {} after {}", provider, connectivity::describe_outage(*outage)),
+                AlertLevel::Info,
+            ),
+            ConnectivityChange::Unchanged => return Ok(()),
         };
         
-        self.send_notification(title, message, level).await
+        let route = self.broadcast_route();
+        self.deliver(OutgoingNotification::new(title, &message, level), &route).await
+    }
+
+    /// Tell the user to log in again; held back by quiet hours like any other warning
+    pub async fn send_session_expired(&mut self) -> AppResult<()> {
+        let notification = OutgoingNotification::new(
+            "Augment Session Expired",
+            "Monitoring is paused. Choose \"Log In to Augment\" in the tray menu to sign in again.",
+            AlertLevel::Warning,
+        );
+        let route = self.broadcast_route();
+        self.deliver(notification, &route).await
+    }
+    
+    /// Send a sample alert to `webhook`, regardless of whether it is enabled
+    pub async fn test_webhook(&self, webhook: &WebhookConfig) -> WebhookDelivery {
+        let alert = WebhookAlert {
+            rule_id: "test".to_string(),
+            rule_name: "Test Alert".to_string(),
+            event: TransitionKind::Fired,
+            severity: AlertLevel::Info,
+            message: "This is a test alert from orb Credit Monitor".to_string(),
+            balance: None,
+            value: None,
+            threshold: 0.0,
+            timestamp: chrono::Utc::now(),
+        };
+        self.webhook_sender.send(webhook, &alert).await
     }
     
     pub fn set_cooldown_duration(&mut self, duration: Duration) {
@@ -168,28 +487,273 @@ impl NotificationManager {
     
     pub async fn test_notifications(&self) -> AppResult<()> {
         // Send test notifications for each level
-        self.send_notification(
+        self.dispatch(&OutgoingNotification::new(
             "Test Notification - Info",
             "This is a test info notification",
             AlertLevel::Info,
-        ).await?;
+        )).await?;
         
         tokio::time::sleep(Duration::from_millis(500)).await;
         
-        self.send_notification(
+        self.dispatch(&OutgoingNotification::new(
             "Test Notification - Warning",
             "This is a test warning notification",
             AlertLevel::Warning,
-        ).await?;
+        )).await?;
         
         tokio::time::sleep(Duration::from_millis(500)).await;
         
-        self.send_notification(
+        self.dispatch(&OutgoingNotification::new(
             "Test Notification - Critical",
             "This is a test critical notification",
             AlertLevel::Critical,
-        ).await?;
+        )).await?;
         
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification_sinks::MemorySink;
+
+    fn transition(rule_id: &str, severity: AlertLevel) -> AlertTransition {
+        AlertTransition {
+            rule_id: rule_id.to_string(),
+            rule_name: format!("Rule {}", rule_id),
+            kind: TransitionKind::Fired,
+            severity,
+            channels: vec![NotificationChannel::Desktop],
+            value: Some(80.0),
+            threshold: 100.0,
+            message: "Balance is low".to_string(),
+        }
+    }
+
+    fn evaluation(rule_id: &str, severity: AlertLevel) -> RuleEvaluation {
+        RuleEvaluation {
+            rule_id: rule_id.to_string(),
+            rule_name: format!("Rule {}", rule_id),
+            severity,
+            channels: vec![NotificationChannel::Desktop],
+            triggered: true,
+            value: Some(80.0),
+            threshold: 100.0,
+            message: "Balance is low".to_string(),
+        }
+    }
+
+    fn batch<'a>(evaluations: &'a [RuleEvaluation], transitions: &'a [AlertTransition]) -> AlertBatch<'a> {
+        static NONE_SNOOZED: std::sync::OnceLock<HashSet<String>> = std::sync::OnceLock::new();
+
+        AlertBatch {
+            rules: &[],
+            evaluations,
+            transitions,
+            snoozed_rule_ids: NONE_SNOOZED.get_or_init(HashSet::new),
+            balance: Some(80),
+            hours_to_depletion: None,
+            portal_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transitions_fan_out_to_sinks() {
+        let memory = MemorySink::new();
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(vec![Box::new(memory.clone()), Box::new(MemorySink::failing("offline"))]);
+
+        let transitions = vec![transition("low", AlertLevel::Warning)];
+        let evaluations = vec![evaluation("low", AlertLevel::Warning)];
+
+        manager.process_alert_transitions(&batch(&evaluations, &transitions), true).await;
+
+        let delivered = memory.delivered();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].title, "Rule low");
+        assert_eq!(delivered[0].level, AlertLevel::Warning);
+
+        let outcomes = manager.delivery_outcomes();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes.iter().filter(|o| o.success).count(), 1);
+        assert_eq!(outcomes.iter().find(|o| !o.success).unwrap().error.as_deref(), Some("Notification error: offline"));
+    }
+
+    /// Answers `requests` requests with 200, each after `delay`
+    async fn serve_slowly(delay: Duration, requests: usize) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for _ in 0..requests {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = socket.read(&mut buf).await;
+                    tokio::time::sleep(delay).await;
+                    let _ = socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n").await;
+                });
+            }
+        });
+        url
+    }
+
+    fn webhook(id: &str, url: &str) -> WebhookConfig {
+        WebhookConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: url.to_string(),
+            enabled: true,
+            headers: HashMap::new(),
+            secret: None,
+            body_template: None,
+            timeout_seconds: 5,
+            max_retries: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_are_delivered_in_the_background() {
+        let url = serve_slowly(Duration::from_millis(300), 1).await;
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(Vec::new());
+        let mut fired = transition("low", AlertLevel::Warning);
+        fired.channels = vec![NotificationChannel::Webhook { id: "ops".to_string() }];
+        let transitions = vec![fired];
+        let evaluations = vec![evaluation("low", AlertLevel::Warning)];
+        manager.set_channels(vec![webhook("ops", &url)], Vec::new(), EmailSettings::default(), Vec::new());
+
+        let started = Instant::now();
+        manager.process_alert_transitions(&batch(&evaluations, &transitions), true).await;
+        assert!(started.elapsed() < Duration::from_millis(300));
+        assert!(manager.delivery_outcomes().is_empty());
+
+        manager.deliveries.wait_idle().await;
+        let outcomes = manager.delivery_outcomes();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].sink, "webhook:ops");
+        assert!(outcomes[0].success, "{:?}", outcomes[0]);
+    }
+
+    /// Quiet hours around the clock, every day
+    fn always_quiet() -> QuietHoursSettings {
+        let noon = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        QuietHoursSettings {
+            enabled: true,
+            timezone: Some("UTC".to_string()),
+            windows: [
+                chrono::Weekday::Mon, chrono::Weekday::Tue, chrono::Weekday::Wed, chrono::Weekday::Thu,
+                chrono::Weekday::Fri, chrono::Weekday::Sat, chrono::Weekday::Sun,
+            ]
+            .into_iter()
+            .map(|weekday| quiet_hours::QuietWindow { weekday, start: noon, end: noon })
+            .collect(),
+            critical_bypass: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold_back_every_channel() {
+        let url = serve_slowly(Duration::ZERO, 1).await;
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(Vec::new());
+        manager.set_quiet_hours(always_quiet());
+        manager.set_channels(vec![webhook("ops", &url)], Vec::new(), EmailSettings::default(), Vec::new());
+        let mut fired = transition("low", AlertLevel::Warning);
+        fired.channels = vec![NotificationChannel::Webhook { id: "ops".to_string() }];
+        let transitions = vec![fired];
+        let evaluations = vec![evaluation("low", AlertLevel::Warning)];
+
+        manager.process_alert_transitions(&batch(&evaluations, &transitions), true).await;
+        manager.deliveries.wait_idle().await;
+        assert!(manager.delivery_outcomes().is_empty());
+        assert_eq!(manager.deferred_notifications().len(), 1);
+
+        manager.set_quiet_hours(QuietHoursSettings::default());
+        manager.flush_deferred().await;
+        manager.deliveries.wait_idle().await;
+        let outcomes = manager.delivery_outcomes();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].sink, "webhook:ops");
+        assert_eq!(outcomes[0].title, "During quiet hours: Rule low");
+        assert!(manager.deferred_notifications().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rules_naming_a_chat_channel_skip_severity_fan_out() {
+        let url = serve_slowly(Duration::ZERO, 3).await;
+        let chat = |id: &str| ChatChannelConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            platform: chat_channels::ChatPlatform::Slack,
+            url: url.clone(),
+            enabled: true,
+            severities: vec![AlertLevel::Warning, AlertLevel::Critical],
+        };
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(Vec::new());
+        manager.set_channels(Vec::new(), vec![chat("team"), chat("oncall")], EmailSettings::default(), Vec::new());
+        let mut targeted = transition("low", AlertLevel::Warning);
+        targeted.channels = vec![NotificationChannel::Chat { id: "team".to_string() }];
+        let transitions = vec![targeted, transition("pacing", AlertLevel::Warning)];
+        let evaluations = vec![evaluation("low", AlertLevel::Warning), evaluation("pacing", AlertLevel::Warning)];
+        manager.process_alert_transitions(&batch(&evaluations, &transitions), true).await;
+        manager.deliveries.wait_idle().await;
+
+        let mut delivered: Vec<(String, String)> = manager.delivery_outcomes().into_iter()
+            .filter(|o| o.success)
+            .map(|o| (o.title, o.sink))
+            .collect();
+        delivered.sort();
+        assert_eq!(delivered, vec![
+            ("Rule low".to_string(), "chat:team".to_string()),
+            ("Rule pacing".to_string(), "chat:oncall".to_string()),
+            ("Rule pacing".to_string(), "chat:team".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_each_channel_is_delivered_once() {
+        const URL: &str = "https://hooks.example.com";
+        let ops = NotificationChannel::Webhook { id: "ops".to_string() };
+        let mut manager = NotificationManager::new();
+        manager.set_channels(vec![webhook("ops", URL), webhook("billing", URL)], Vec::new(), EmailSettings::default(), vec![ops.clone()]);
+
+        let names = |channels: &[NotificationChannel]| manager.remote_sinks(channels, AlertLevel::Warning)
+            .iter()
+            .map(|sink| sink.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names(&[ops.clone(), NotificationChannel::Desktop, ops.clone()]), vec!["webhook:ops"]);
+        assert_eq!(names(&manager.broadcast_route()), vec!["webhook:ops"]);
+        assert!(names(&[NotificationChannel::Desktop]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_expiry_waits_for_quiet_hours() {
+        let memory = MemorySink::new();
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(vec![Box::new(memory.clone())]);
+        manager.set_quiet_hours(always_quiet());
+
+        manager.send_session_expired().await.unwrap();
+        assert!(memory.delivered().is_empty());
+        assert_eq!(manager.deferred_notifications().len(), 1);
+
+        manager.set_quiet_hours(QuietHoursSettings::default());
+        manager.flush_deferred().await;
+        assert_eq!(memory.delivered().len(), 1);
+        assert_eq!(memory.delivered()[0].title, "During quiet hours: Augment Session Expired");
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_only_when_every_sink_fails() {
+        let mut manager = NotificationManager::new();
+        manager.set_sinks(vec![Box::new(MemorySink::failing("down"))]);
+        assert!(manager.send_notification("Title", "Body", AlertLevel::Info).await.is_err());
+
+        manager.set_sinks(vec![Box::new(MemorySink::failing("down")), Box::new(MemorySink::new())]);
+        assert!(manager.send_notification("Title", "Body", AlertLevel::Info).await.is_ok());
+    }
+}
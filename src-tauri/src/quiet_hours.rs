@@ -0,0 +1,270 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use crate::alert_rules::NotificationChannel;
+use crate::analytics::AlertLevel;
+use crate::error::{AppError, AppResult};
+
+/// A do-not-disturb window starting on `weekday`. When `end` is not after `start` the window
+/// runs past midnight into the next day (e.g. 22:00 - 07:00).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietWindow {
+    pub weekday: Weekday,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHoursSettings {
+    pub enabled: bool,
+    /// IANA timezone such as "Europe/Berlin"; the system timezone when unset
+    pub timezone: Option<String>,
+    pub windows: Vec<QuietWindow>,
+    /// Critical alerts are shown immediately even during quiet hours
+    pub critical_bypass: bool,
+}
+
+impl Default for QuietHoursSettings {
+    fn default() -> Self {
+        let start = NaiveTime::from_hms_opt(22, 0, 0).expect("valid time");
+        let end = NaiveTime::from_hms_opt(7, 0, 0).expect("valid time");
+
+        Self {
+            enabled: false,
+            timezone: None,
+            windows: [
+                Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+                Weekday::Fri, Weekday::Sat, Weekday::Sun,
+            ]
+            .into_iter()
+            .map(|weekday| QuietWindow { weekday, start, end })
+            .collect(),
+            critical_bypass: true,
+        }
+    }
+}
+
+impl QuietHoursSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(timezone) = &self.timezone {
+            timezone.parse::<Tz>().map_err(|_| AppError::Config(
+                config::ConfigError::Message(format!("Unknown timezone '{}'", timezone))
+            ))?;
+        }
+        if self.windows.iter().any(|w| w.start == w.end) {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Quiet hours windows must have different start and end times".to_string())
+            ));
+        }
+        Ok(())
+    }
+
+    /// End of the quiet window covering `now`, or `None` outside quiet hours
+    pub fn active_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.enabled {
+            return None;
+        }
+
+        match self.timezone.as_deref().and_then(|tz| tz.parse::<Tz>().ok()) {
+            Some(tz) => active_until_in(&self.windows, &tz, now),
+            None => active_until_in(&self.windows, &Local, now),
+        }
+    }
+
+    /// Whether an alert of `level` has to wait for the end of quiet hours at `now`
+    pub fn should_defer(&self, level: AlertLevel, now: DateTime<Utc>) -> bool {
+        if level == AlertLevel::Critical && self.critical_bypass {
+            return false;
+        }
+        self.active_until(now).is_some()
+    }
+}
+
+fn active_until_in<T: TimeZone>(windows: &[QuietWindow], tz: &T, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let local_now = now.with_timezone(tz).naive_local();
+    let end = window_end_at(windows, local_now)?;
+
+    // DST gaps and overlaps: take the earliest valid instant, or shift past a gap
+    tz.from_local_datetime(&end)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(end + Duration::hours(1))).earliest())
+        .map(|end| end.with_timezone(&Utc))
+}
+
+/// Local end time of the window containing the local time `now`; windows that chain into
+/// each other are merged so the result is the end of the whole quiet period.
+fn window_end_at(windows: &[QuietWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let mut end = containing_window_end(windows, now)?;
+
+    // Follow adjacent windows (e.g. a weekend window starting when the Friday night one ends)
+    for _ in 0..windows.len() {
+        match containing_window_end(windows, end) {
+            Some(next) if next > end => end = next,
+            _ => break,
+        }
+    }
+
+    Some(end)
+}
+
+fn containing_window_end(windows: &[QuietWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let today = now.date();
+
+    [today - Duration::days(1), today]
+        .into_iter()
+        .flat_map(|day| {
+            windows.iter()
+                .filter(move |w| w.weekday == day.weekday())
+                .map(move |w| {
+                    let start = day.and_time(w.start);
+                    let end = if w.end > w.start {
+                        day.and_time(w.end)
+                    } else {
+                        (day + Duration::days(1)).and_time(w.end)
+                    };
+                    (start, end)
+                })
+        })
+        .filter(|(start, end)| *start <= now && now < *end)
+        .map(|(_, end)| end)
+        .max()
+}
+
+/// An alert held back during quiet hours
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeferredNotification {
+    pub title: String,
+    pub message: String,
+    pub level: AlertLevel,
+    pub raised_at: DateTime<Utc>,
+    /// Where the alert would have gone; notifications deferred before this was recorded were desktop-only
+    #[serde(default = "desktop_only")]
+    pub channels: Vec<NotificationChannel>,
+}
+
+fn desktop_only() -> Vec<NotificationChannel> {
+    vec![NotificationChannel::Desktop]
+}
+
+/// Every channel any of `deferred` was routed to, in first-seen order
+pub fn combined_route(deferred: &[DeferredNotification]) -> Vec<NotificationChannel> {
+    let mut route: Vec<NotificationChannel> = Vec::new();
+    for channel in deferred.iter().flat_map(|d| &d.channels) {
+        if !route.contains(channel) {
+            route.push(channel.clone());
+        }
+    }
+    route
+}
+
+/// Title, body and level of the single notification summarizing everything deferred
+pub fn summarize(deferred: &[DeferredNotification]) -> Option<(String, String, AlertLevel)> {
+    let level = deferred.iter().map(|d| d.level).max()?;
+
+    let title = if deferred.len() == 1 {
+        format!("During quiet hours: {}", deferred[0].title)
+    } else {
+        format!("{} alerts during quiet hours", deferred.len())
+    };
+
+    let body = if deferred.len() == 1 {
+        deferred[0].message.clone()
+    } else {
+        const SHOWN: usize = 4;
+        let mut lines: Vec<String> = deferred.iter()
+            .take(SHOWN)
+            .map(|d| format!("• {}", d.title))
+            .collect();
+        if deferred.len() > SHOWN {
+            lines.push(format!("…and {} more", deferred.len() - SHOWN));
+        }
+        lines.join("\n")
+    };
+
+    Some((title, body, level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn local(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        // 2025-11-03 is a Monday
+        NaiveDate::from_ymd_opt(2025, 11, day).unwrap().and_time(time(h, m))
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        let windows = vec![QuietWindow { weekday: Weekday::Mon, start: time(22, 0), end: time(7, 0) }];
+
+        assert_eq!(window_end_at(&windows, local(3, 23, 0)), Some(local(4, 7, 0)));
+        assert_eq!(window_end_at(&windows, local(4, 2, 0)), Some(local(4, 7, 0)));
+        assert_eq!(window_end_at(&windows, local(4, 7, 0)), None);
+        assert_eq!(window_end_at(&windows, local(4, 23, 0)), None);
+    }
+
+    #[test]
+    fn test_adjacent_windows_merge() {
+        let windows = vec![
+            QuietWindow { weekday: Weekday::Fri, start: time(18, 0), end: time(0, 0) },
+            QuietWindow { weekday: Weekday::Sat, start: time(0, 0), end: time(12, 0) },
+        ];
+        assert_eq!(window_end_at(&windows, local(7, 20, 0)), Some(local(8, 12, 0)));
+    }
+
+    #[test]
+    fn test_timezone_and_critical_bypass() {
+        let settings = QuietHoursSettings {
+            enabled: true,
+            timezone: Some("America/New_York".to_string()),
+            windows: vec![QuietWindow { weekday: Weekday::Mon, start: time(22, 0), end: time(7, 0) }],
+            critical_bypass: true,
+        };
+        assert!(settings.validate().is_ok());
+
+        // 2025-11-04 03:00 UTC is Monday 22:00 in New York (EST, UTC-5)
+        let now = Utc.with_ymd_and_hms(2025, 11, 4, 3, 0, 0).unwrap();
+        assert_eq!(settings.active_until(now), Some(Utc.with_ymd_and_hms(2025, 11, 4, 12, 0, 0).unwrap()));
+        assert!(settings.should_defer(AlertLevel::Warning, now));
+        assert!(!settings.should_defer(AlertLevel::Critical, now));
+
+        let strict = QuietHoursSettings { critical_bypass: false, ..settings.clone() };
+        assert!(strict.should_defer(AlertLevel::Critical, now));
+
+        let afternoon = Utc.with_ymd_and_hms(2025, 11, 4, 18, 0, 0).unwrap();
+        assert!(!settings.should_defer(AlertLevel::Warning, afternoon));
+    }
+
+    #[test]
+    fn test_summary() {
+        let deferred: Vec<DeferredNotification> = (0..6)
+            .map(|i| DeferredNotification {
+                title: format!("Alert {}", i),
+                message: String::new(),
+                level: if i == 2 { AlertLevel::Warning } else { AlertLevel::Info },
+                raised_at: Utc::now(),
+                channels: if i == 3 {
+                    vec![NotificationChannel::Webhook { id: "ops".to_string() }, NotificationChannel::Desktop]
+                } else {
+                    vec![NotificationChannel::Desktop]
+                },
+            })
+            .collect();
+
+        let (title, body, level) = summarize(&deferred).unwrap();
+        assert_eq!(title, "6 alerts during quiet hours");
+        assert_eq!(level, AlertLevel::Warning);
+        assert!(body.ends_with("…and 2 more"));
+        assert!(summarize(&[]).is_none());
+        assert_eq!(combined_route(&deferred), vec![
+            NotificationChannel::Desktop,
+            NotificationChannel::Webhook { id: "ops".to_string() },
+        ]);
+    }
+}
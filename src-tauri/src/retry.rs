@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::error::{AppError, AppResult};
+
+/// How idempotent requests are retried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with every further attempt
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Timeout of a single attempt
+    pub request_timeout_seconds: u64,
+    /// Time budget for all attempts of one request, including waits
+    pub deadline_seconds: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 10_000,
+            request_timeout_seconds: 30,
+            deadline_seconds: 60,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.max_attempts == 0 {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Retry policy needs at least 1 attempt".to_string())
+            ));
+        }
+        if self.request_timeout_seconds == 0 || self.deadline_seconds < self.request_timeout_seconds {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Request deadline must be at least the per-request timeout".to_string())
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_seconds)
+    }
+
+    /// Errors worth another attempt: the request may succeed if simply repeated
+    pub fn is_retryable(error: &AppError) -> bool {
+        matches!(
+            error,
+            AppError::Timeout | AppError::Network(_) | AppError::RateLimit { .. } | AppError::Server { .. }
+        )
+    }
+
+    /// Exponential backoff for the retry after `attempt`, with half of it randomized
+    pub fn backoff(&self, attempt: u32, jitter: f64) -> Duration {
+        let exponential = self.base_delay_ms.saturating_mul(1u64 << attempt.saturating_sub(1).min(20));
+        let delay = exponential.min(self.max_delay_ms) as f64;
+        Duration::from_millis((delay / 2.0 + delay / 2.0 * jitter.clamp(0.0, 1.0)) as u64)
+    }
+
+    /// Wait before retrying after `error`; the server's Retry-After wins over the backoff
+    pub fn delay_for(&self, attempt: u32, error: &AppError, jitter: f64) -> Duration {
+        let retry_after = match error {
+            AppError::RateLimit { retry_after_secs } => *retry_after_secs,
+            AppError::Server { status: 503, retry_after_secs, .. } => *retry_after_secs,
+            _ => None,
+        };
+        retry_after
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| self.backoff(attempt, jitter))
+    }
+
+    /// Run `operation` until it succeeds, fails with a non-retryable error, runs out of
+    /// attempts or would pass the deadline
+    pub async fn run<T, F, Fut>(&self, what: &str, mut operation: F) -> AppResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let deadline = Instant::now() + Duration::from_secs(self.deadline_seconds);
+        let mut attempt = 1;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let result = match tokio::time::timeout(remaining.min(self.request_timeout()), operation()).await {
+                Ok(result) => result,
+                Err(_) => Err(AppError::Timeout),
+            };
+
+            let error = match result {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            if attempt >= self.max_attempts || !Self::is_retryable(&error) {
+                return Err(error);
+            }
+
+            let delay = self.delay_for(attempt, &error, jitter());
+            if Instant::now() + delay >= deadline {
+                tracing::warn!("⏱️ {}: no time left to retry after: {}", what, error);
+                return Err(error);
+            }
+
+            tracing::warn!("🔁 {} failed (attempt {}/{}): {} - retrying in {:?}", what, attempt, self.max_attempts, error, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Cheap 0..1 jitter; spreading retries out doesn't need a real RNG
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos % 1_000_000) as f64 / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 1,
+            max_delay_ms: 5,
+            request_timeout_seconds: 1,
+            deadline_seconds: 5,
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2, 1.0), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(2, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(10, 1.0), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn test_retry_after_wins() {
+        let policy = RetryPolicy::default();
+        let limited = AppError::RateLimit { retry_after_secs: Some(7) };
+        assert_eq!(policy.delay_for(1, &limited, 0.5), Duration::from_secs(7));
+
+        let unavailable = AppError::Server { status: 503, message: String::new(), retry_after_secs: Some(2) };
+        assert_eq!(policy.delay_for(1, &unavailable, 0.5), Duration::from_secs(2));
+
+        assert!(!RetryPolicy::is_retryable(&AppError::Unauthorized(String::new())));
+        assert!(!RetryPolicy::is_retryable(&AppError::BadPayload(String::new())));
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result = fast_policy().run("test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(AppError::Network("connection reset".to_string())),
+                1 => Err(AppError::Server { status: 502, message: String::new(), retry_after_secs: None }),
+                _ => Ok(42),
+            }
+        }).await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_on_permanent_errors_and_deadline() {
+        let calls = AtomicU32::new(0);
+        let result: AppResult<()> = fast_policy().run("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::Unauthorized(String::new()))
+        }).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A Retry-After beyond the deadline is not waited for
+        let calls = AtomicU32::new(0);
+        let result: AppResult<()> = fast_policy().run("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::RateLimit { retry_after_secs: Some(60) })
+        }).await;
+        assert!(matches!(result, Err(AppError::RateLimit { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,250 @@
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Largest raw payload kept for a failed decode
+const MAX_CAPTURED_PAYLOAD: usize = 64 * 1024;
+
+/// JSON type of a field, as far as drift detection cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonKind {
+    String,
+    Number,
+    Bool,
+    Array,
+    Object,
+}
+
+impl JsonKind {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::String(_) => Some(JsonKind::String),
+            Value::Number(_) => Some(JsonKind::Number),
+            Value::Bool(_) => Some(JsonKind::Bool),
+            Value::Array(_) => Some(JsonKind::Array),
+            Value::Object(_) => Some(JsonKind::Object),
+        }
+    }
+}
+
+/// A field an API response is expected to contain
+pub struct ExpectedField {
+    /// Name in the JSON payload (camelCase)
+    pub name: &'static str,
+    pub kind: JsonKind,
+    /// Whether `null` is a normal value rather than drift
+    pub nullable: bool,
+}
+
+impl ExpectedField {
+    pub const fn new(name: &'static str, kind: JsonKind) -> Self {
+        Self { name, kind, nullable: false }
+    }
+
+    pub const fn nullable(name: &'static str, kind: JsonKind) -> Self {
+        Self { name, kind, nullable: true }
+    }
+}
+
+/// Response types that know which fields the API used to send
+pub trait ExpectedSchema {
+    const FIELDS: &'static [ExpectedField];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RetypedField {
+    pub field: String,
+    pub expected: JsonKind,
+    /// `None` when the field came back as `null`
+    pub found: Option<JsonKind>,
+}
+
+/// How a payload differs from the expected schema
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SchemaDrift {
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+    pub retyped: Vec<RetypedField>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.retyped.is_empty()
+    }
+
+    /// One-line summary for logs and error messages
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.missing.is_empty() {
+            parts.push(format!("missing: {}", self.missing.join(", ")));
+        }
+        if !self.unexpected.is_empty() {
+            parts.push(format!("unexpected: {}", self.unexpected.join(", ")));
+        }
+        if !self.retyped.is_empty() {
+            let retyped: Vec<String> = self.retyped.iter()
+                .map(|r| format!(
+                    "{} ({:?} -> {})",
+                    r.field,
+                    r.expected,
+                    r.found.map(|kind| format!("{:?}", kind)).unwrap_or_else(|| "null".to_string())
+                ))
+                .collect();
+            parts.push(format!("retyped: {}", retyped.join(", ")));
+        }
+        if parts.is_empty() {
+            "no schema drift".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// Compare the top-level fields of `payload` with `expected`
+pub fn detect(payload: &Value, expected: &[ExpectedField]) -> SchemaDrift {
+    let mut drift = SchemaDrift::default();
+    let Some(object) = payload.as_object() else {
+        drift.missing = expected.iter().map(|field| field.name.to_string()).collect();
+        return drift;
+    };
+
+    for field in expected {
+        match object.get(field.name) {
+            None => drift.missing.push(field.name.to_string()),
+            Some(value) => {
+                let found = JsonKind::of(value);
+                let matches = match found {
+                    Some(kind) => kind == field.kind,
+                    None => field.nullable,
+                };
+                if !matches {
+                    drift.retyped.push(RetypedField {
+                        field: field.name.to_string(),
+                        expected: field.kind,
+                        found,
+                    });
+                }
+            }
+        }
+    }
+
+    drift.unexpected = object.keys()
+        .filter(|key| !expected.iter().any(|field| field.name == key.as_str()))
+        .cloned()
+        .collect();
+    drift
+}
+
+/// What was seen the last time an endpoint's payload didn't match its schema
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodeDiagnostic {
+    pub endpoint: String,
+    pub observed_at: DateTime<Utc>,
+    pub drift: SchemaDrift,
+    /// Decode error, if the payload could not be used at all
+    pub error: Option<String>,
+    /// Payload as received (truncated), kept only when decoding failed
+    pub raw_payload: Option<String>,
+}
+
+impl DecodeDiagnostic {
+    pub fn new(endpoint: &str, drift: SchemaDrift, error: Option<String>, body: Option<&str>) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            observed_at: Utc::now(),
+            drift,
+            error,
+            raw_payload: body.map(truncate_payload),
+        }
+    }
+}
+
+fn truncate_payload(body: &str) -> String {
+    if body.len() <= MAX_CAPTURED_PAYLOAD {
+        return body.to_string();
+    }
+    let mut end = MAX_CAPTURED_PAYLOAD;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes total)", &body[..end], body.len())
+}
+
+/// `deserialize_with` for non-essential fields: a value of the wrong type becomes the default
+/// instead of failing the whole response
+pub fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIELDS: &[ExpectedField] = &[
+        ExpectedField::new("planName", JsonKind::String),
+        ExpectedField::new("trialGrant", JsonKind::Number),
+        ExpectedField::nullable("trialPeriodEnd", JsonKind::String),
+        ExpectedField::new("planFacts", JsonKind::Array),
+    ];
+
+    #[test]
+    fn test_detect_reports_each_kind_of_drift() {
+        let payload = json!({
+            "planName": "Developer",
+            "trialGrant": "500",
+            "trialPeriodEnd": null,
+            "seatLimit": 5
+        });
+        let drift = detect(&payload, FIELDS);
+
+        assert_eq!(drift.missing, vec!["planFacts"]);
+        assert_eq!(drift.unexpected, vec!["seatLimit"]);
+        assert_eq!(drift.retyped, vec![RetypedField {
+            field: "trialGrant".to_string(),
+            expected: JsonKind::Number,
+            found: Some(JsonKind::String),
+        }]);
+        assert_eq!(
+            drift.summary(),
+            "missing: planFacts; unexpected: seatLimit; retyped: trialGrant (Number -> String)"
+        );
+    }
+
+    #[test]
+    fn test_matching_payload_has_no_drift() {
+        let payload = json!({ "planName": "Pro", "trialGrant": 0, "trialPeriodEnd": "2025-01-01", "planFacts": [] });
+        assert!(detect(&payload, FIELDS).is_empty());
+        assert_eq!(detect(&json!([]), FIELDS).missing.len(), FIELDS.len());
+    }
+
+    #[test]
+    fn test_or_default_tolerates_retyped_values() {
+        #[derive(Deserialize)]
+        struct Lenient {
+            #[serde(default, deserialize_with = "or_default")]
+            count: i64,
+        }
+
+        let lenient: Lenient = serde_json::from_value(json!({ "count": "many" })).unwrap();
+        assert_eq!(lenient.count, 0);
+        let lenient: Lenient = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(lenient.count, 0);
+    }
+
+    #[test]
+    fn test_raw_payload_is_truncated() {
+        let body = "é".repeat(MAX_CAPTURED_PAYLOAD);
+        let diagnostic = DecodeDiagnostic::new("User", SchemaDrift::default(), Some("bad".to_string()), Some(&body));
+        let raw = diagnostic.raw_payload.unwrap();
+        assert!(raw.len() < body.len());
+        assert!(raw.ends_with(&format!("({} bytes total)", body.len())));
+    }
+}
@@ -1,7 +1,12 @@
 use reqwest::{Client, header::HeaderMap};
 use scraper::{Html, Selector};
+use std::ffi::OsStr;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use crate::connectivity;
 use crate::error::{AppError, AppResult};
+use crate::fixtures::{FixtureMode, FixtureStore};
+use crate::network::NetworkSettings;
 use headless_chrome::{Browser, LaunchOptions};
 
 pub struct orbScraper {
@@ -10,11 +15,15 @@ pub struct orbScraper {
     retry_attempts: u32,
     timeout_seconds: u64,
     use_browser: bool,
+    /// Proxy switches for the headless browser
+    browser_args: Vec<String>,
+    /// Records or replays the portal API requests when started in fixture mode
+    fixtures: Arc<FixtureStore>,
 }
 
 impl orbScraper {
-    pub async fn new() -> AppResult<Self> {
-        let client = Client::builder()
+    pub async fn new(network: &NetworkSettings) -> AppResult<Self> {
+        let client = network.client_builder()?
             .timeout(Duration::from_secs(30))
             .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
             .build()?;
@@ -29,8 +38,15 @@ impl orbScraper {
             retry_attempts: 3,
             timeout_seconds: 30,
             use_browser: true, // Enable browser-based scraping for JavaScript content
+            browser_args: network.browser_args(),
+            fixtures: Arc::new(FixtureStore::off()),
         })
     }
+
+    pub fn with_fixtures(mut self, fixtures: Arc<FixtureStore>) -> Self {
+        self.fixtures = fixtures;
+        self
+    }
     
     pub async fn fetch_balance(&self, token: &str) -> AppResult<u32> {
         let url = format!("https://portal.withorb.com/view?token={}", token);
@@ -43,6 +59,17 @@ impl orbScraper {
                     tracing::info!("✅ Successfully fetched balance via API: {} (attempt {})", balance, attempt);
                     return Ok(balance);
                 }
+                // The portal is unreachable, so the browser would fail the same way but less clearly
+                Err(e) if connectivity::classify(&e).is_some() => {
+                    tracing::warn!("Attempt {} failed: {}", attempt, e);
+                    if attempt == self.retry_attempts {
+                        return Err(e);
+                    }
+                    tokio::time::sleep(Duration::from_secs(2_u64.pow(attempt - 1))).await;
+                    continue;
+                }
+                // Only the portal API requests have fixtures
+                Err(e) if self.fixtures.mode() == FixtureMode::Replay => return Err(e),
                 Err(e) => {
                     tracing::info!("API approach failed: {}, trying browser...", e);
                 }
@@ -85,7 +112,7 @@ impl orbScraper {
             .headless(true)
             .sandbox(false)
             .window_size(Some((1920, 1080)))
-
+            .args(self.browser_args.iter().map(OsStr::new).collect())
             .build()
             .map_err(|e| AppError::Scraping(format!("Failed to build launch options: {}", e)))?;
 
@@ -340,20 +367,16 @@ impl orbScraper {
 
         // Get customer information first
         tracing::info!("Fetching customer information from: {}", customer_info_url);
-        let customer_response = self.client
+        let customer_request = self.client
             .get(&customer_info_url)
-            .headers(headers.clone())
-            .send()
-            .await
-            .map_err(|e| AppError::Scraping(format!("Failed to fetch customer info: {}", e)))?;
+            .headers(headers.clone());
+        let customer_response = self.fixtures.send(&customer_info_url, customer_request).await?;
 
         if !customer_response.status().is_success() {
-            return Err(AppError::Scraping(format!("Customer info API returned status: {}", customer_response.status())));
+            return Err(portal_status_error(customer_response.status(), "Customer info"));
         }
 
-        let customer_data: serde_json::Value = customer_response
-            .json()
-            .await
+        let customer_data: serde_json::Value = serde_json::from_str(&customer_response.body)
             .map_err(|e| AppError::Scraping(format!("Failed to parse customer info JSON: {}", e)))?;
 
         tracing::info!("Customer data received successfully");
@@ -383,20 +406,16 @@ impl orbScraper {
         );
 
         tracing::info!("Fetching ledger summary from: {}", ledger_url);
-        let ledger_response = self.client
+        let ledger_request = self.client
             .get(&ledger_url)
-            .headers(headers)
-            .send()
-            .await
-            .map_err(|e| AppError::Scraping(format!("Failed to fetch ledger summary: {}", e)))?;
+            .headers(headers);
+        let ledger_response = self.fixtures.send(&ledger_url, ledger_request).await?;
 
         if !ledger_response.status().is_success() {
-            return Err(AppError::Scraping(format!("Ledger API returned status: {}", ledger_response.status())));
+            return Err(portal_status_error(ledger_response.status(), "Ledger"));
         }
 
-        let ledger_data: serde_json::Value = ledger_response
-            .json()
-            .await
+        let ledger_data: serde_json::Value = serde_json::from_str(&ledger_response.body)
             .map_err(|e| AppError::Scraping(format!("Failed to parse ledger JSON: {}", e)))?;
 
         tracing::info!("Ledger data received successfully");
@@ -1015,6 +1034,58 @@ impl orbScraper {
     }
 }
 
+/// The scraper for the current network settings, rebuilt when they change. When the settings
+/// can't be applied, fetches fail with that error instead of going around the proxy.
+pub struct ScraperHandle {
+    current: RwLock<Result<Arc<orbScraper>, String>>,
+    fixtures: Arc<FixtureStore>,
+}
+
+impl ScraperHandle {
+    pub async fn new(network: &NetworkSettings, fixtures: Arc<FixtureStore>) -> Self {
+        let current = match orbScraper::new(network).await {
+            Ok(scraper) => Ok(Arc::new(scraper.with_fixtures(fixtures.clone()))),
+            Err(e) => {
+                tracing::error!("❌ Failed to apply network settings to the Orb scraper: {}", e);
+                Err(e.to_string())
+            }
+        };
+        Self { current: RwLock::new(current), fixtures }
+    }
+
+    /// Rebuild the scraper for `network`; on error the current one stays in use
+    pub async fn set_network(&self, network: &NetworkSettings) -> AppResult<()> {
+        let scraper = orbScraper::new(network).await?.with_fixtures(self.fixtures.clone());
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Ok(Arc::new(scraper));
+        tracing::info!("🌐 Orb scraper now uses the updated network settings");
+        Ok(())
+    }
+
+    pub fn get(&self) -> AppResult<Arc<orbScraper>> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone().map_err(|message| {
+            AppError::Config(config::ConfigError::Message(format!("Network settings could not be applied: {}", message)))
+        })
+    }
+
+    pub async fn fetch_balance(&self, token: &str) -> AppResult<u32> {
+        self.get()?.fetch_balance(token).await
+    }
+}
+
+/// Error for a non-2xx portal response; server-side failures count as the portal being down
+fn portal_status_error(status: reqwest::StatusCode, endpoint: &str) -> AppError {
+    let message = format!("{} API returned status: {}", endpoint, status);
+    match status {
+        reqwest::StatusCode::REQUEST_TIMEOUT | reqwest::StatusCode::GATEWAY_TIMEOUT => AppError::Timeout,
+        status if status.is_server_error() => AppError::Server {
+            status: status.as_u16(),
+            message,
+            retry_after_secs: None,
+        },
+        _ => AppError::Scraping(message),
+    }
+}
+
 // Add regex dependency to Cargo.toml
 #[cfg(test)]
 mod tests {
@@ -1022,7 +1093,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_parse_balance_from_html() {
-        let scraper = orbScraper::new().await.unwrap();
+        let scraper = orbScraper::new(&NetworkSettings::default()).await.unwrap();
 
         let test_html = r#"
             <div>
@@ -1037,11 +1108,47 @@ mod tests {
 
     #[tokio::test]
     async fn test_extract_number_from_text() {
-        let scraper = orbScraper::new().await.unwrap();
+        let scraper = orbScraper::new(&NetworkSettings::default()).await.unwrap();
 
         assert_eq!(scraper.extract_number_from_text("2,683 User Messages"), Some(2683));
         assert_eq!(scraper.extract_number_from_text("Balance: 1,234"), Some(1234));
         assert_eq!(scraper.extract_number_from_text("5000 credits remaining"), Some(5000));
         assert_eq!(scraper.extract_number_from_text("No numbers here"), None);
     }
+
+    #[test]
+    fn test_portal_outages_are_connectivity_failures() {
+        let outage = portal_status_error(reqwest::StatusCode::BAD_GATEWAY, "Ledger");
+        assert_eq!(connectivity::classify(&outage), Some(connectivity::FailureKind::Server));
+        assert_eq!(
+            connectivity::classify(&portal_status_error(reqwest::StatusCode::GATEWAY_TIMEOUT, "Ledger")),
+            Some(connectivity::FailureKind::Timeout)
+        );
+
+        let rejected = portal_status_error(reqwest::StatusCode::NOT_FOUND, "Customer info");
+        assert!(matches!(rejected, AppError::Scraping(_)));
+        assert_eq!(connectivity::classify(&rejected), None);
+    }
+
+    #[tokio::test]
+    async fn test_unusable_network_settings_are_reported() {
+        let broken = NetworkSettings {
+            proxy: crate::network::ProxySettings {
+                mode: crate::network::ProxyMode::Manual,
+                url: None,
+                ..Default::default()
+            },
+            ..NetworkSettings::default()
+        };
+
+        let handle = ScraperHandle::new(&broken, Arc::new(FixtureStore::off())).await;
+        assert!(matches!(handle.get(), Err(AppError::Config(_))));
+
+        handle.set_network(&NetworkSettings::default()).await.unwrap();
+        assert!(handle.get().is_ok());
+
+        // A failed update keeps the working scraper
+        assert!(handle.set_network(&broken).await.is_err());
+        assert!(handle.get().is_ok());
+    }
 }
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Lifecycle of the stored Augment session as seen by the monitoring loop
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SessionStatus {
+    Active,
+    /// Auth failures seen, but not enough yet to give up on the session
+    Failing { consecutive_failures: u32 },
+    /// Polling is paused until the user logs in again
+    Expired { since: DateTime<Utc> },
+}
+
+/// What the caller should do after recording a poll result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionChange {
+    Unchanged,
+    /// The session just crossed the failure threshold; notify once
+    Expired,
+    /// A previously expired session works again
+    Restored,
+}
+
+pub struct SessionTracker {
+    consecutive_failures: u32,
+    failure_threshold: u32,
+    expired_since: Option<DateTime<Utc>>,
+}
+
+impl SessionTracker {
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            consecutive_failures: 0,
+            failure_threshold: failure_threshold.max(1),
+            expired_since: None,
+        }
+    }
+
+    pub fn set_failure_threshold(&mut self, failure_threshold: u32) {
+        self.failure_threshold = failure_threshold.max(1);
+    }
+
+    /// Forget all failures, e.g. after the user logs out or in again
+    pub fn reset(&mut self) {
+        self.consecutive_failures = 0;
+        self.expired_since = None;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expired_since.is_some()
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        match self.expired_since {
+            Some(since) => SessionStatus::Expired { since },
+            None if self.consecutive_failures > 0 => SessionStatus::Failing {
+                consecutive_failures: self.consecutive_failures,
+            },
+            None => SessionStatus::Active,
+        }
+    }
+
+    pub fn record_success(&mut self) -> SessionChange {
+        self.consecutive_failures = 0;
+        match self.expired_since.take() {
+            Some(_) => SessionChange::Restored,
+            None => SessionChange::Unchanged,
+        }
+    }
+
+    pub fn record_auth_failure(&mut self, now: DateTime<Utc>) -> SessionChange {
+        self.consecutive_failures += 1;
+        if self.expired_since.is_none() && self.consecutive_failures >= self.failure_threshold {
+            self.expired_since = Some(now);
+            return SessionChange::Expired;
+        }
+        SessionChange::Unchanged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expires_once_after_threshold() {
+        let mut tracker = SessionTracker::new(3);
+        let now = Utc::now();
+
+        assert_eq!(tracker.record_auth_failure(now), SessionChange::Unchanged);
+        assert_eq!(tracker.record_auth_failure(now), SessionChange::Unchanged);
+        assert_eq!(tracker.status(), SessionStatus::Failing { consecutive_failures: 2 });
+
+        assert_eq!(tracker.record_auth_failure(now), SessionChange::Expired);
+        assert_eq!(tracker.record_auth_failure(now), SessionChange::Unchanged);
+        assert_eq!(tracker.status(), SessionStatus::Expired { since: now });
+    }
+
+    #[test]
+    fn test_success_resets_and_restores() {
+        let mut tracker = SessionTracker::new(2);
+        tracker.record_auth_failure(Utc::now());
+        assert_eq!(tracker.record_success(), SessionChange::Unchanged);
+
+        // Failures must be consecutive
+        tracker.record_auth_failure(Utc::now());
+        assert!(!tracker.is_expired());
+
+        tracker.record_auth_failure(Utc::now());
+        assert!(tracker.is_expired());
+        assert_eq!(tracker.record_success(), SessionChange::Restored);
+        assert_eq!(tracker.status(), SessionStatus::Active);
+    }
+
+    #[test]
+    fn test_relogin_after_expiry() {
+        let mut tracker = SessionTracker::new(2);
+        tracker.record_auth_failure(Utc::now());
+        tracker.record_auth_failure(Utc::now());
+        assert!(tracker.is_expired());
+
+        // Logging in again, by any route, records a success
+        assert_eq!(tracker.record_success(), SessionChange::Restored);
+        assert_eq!(tracker.status(), SessionStatus::Active);
+
+        // The new session gets the full threshold before it expires
+        assert_eq!(tracker.record_auth_failure(Utc::now()), SessionChange::Unchanged);
+        assert_eq!(tracker.record_auth_failure(Utc::now()), SessionChange::Expired);
+
+        // A second login while already active is not a restore
+        tracker.record_success();
+        assert_eq!(tracker.record_success(), SessionChange::Unchanged);
+    }
+}
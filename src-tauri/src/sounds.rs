@@ -0,0 +1,381 @@
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use crate::analytics::AlertLevel;
+use crate::error::{AppError, AppResult};
+
+const SAMPLE_RATE: u32 = 22_050;
+/// How long a player gets to fail (no audio server, unreadable file) before it counts as playing
+const PLAYER_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// 0.0 (silent) to 1.0 (full volume)
+    pub volume: f32,
+    /// Custom sound files per level; the bundled sound is used when unset or missing
+    pub info_sound: Option<String>,
+    pub warning_sound: Option<String>,
+    pub critical_sound: Option<String>,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.7,
+            info_sound: None,
+            warning_sound: None,
+            critical_sound: None,
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(AppError::Config(
+                config::ConfigError::Message("Sound volume must be between 0 and 1".to_string())
+            ));
+        }
+        // A file that moved or lives on an unmounted drive falls back to the bundled sound
+        for path in [&self.info_sound, &self.warning_sound, &self.critical_sound].into_iter().flatten() {
+            if !Path::new(path).is_file() {
+                tracing::warn!("⚠️ Sound file not found, the built-in sound will be used: {}", path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Custom sound file for `level`, if one is set and still exists
+    fn custom_sound(&self, level: AlertLevel) -> Option<&str> {
+        let path = match level {
+            AlertLevel::Info => self.info_sound.as_deref(),
+            AlertLevel::Warning => self.warning_sound.as_deref(),
+            AlertLevel::Critical => self.critical_sound.as_deref(),
+        }?;
+
+        if Path::new(path).is_file() {
+            Some(path)
+        } else {
+            tracing::warn!("⚠️ Sound file not found, playing the built-in sound: {}", path);
+            None
+        }
+    }
+}
+
+/// Freedesktop sound theme name used as the notification hint when nothing can be played directly
+pub fn sound_theme_name(level: AlertLevel) -> &'static str {
+    match level {
+        AlertLevel::Info => "message-new-instant",
+        AlertLevel::Warning => "dialog-warning",
+        AlertLevel::Critical => "alarm-clock-elapsed",
+    }
+}
+
+/// Tone sequence (frequency Hz, duration ms) of the bundled sound for each level
+fn tone_pattern(level: AlertLevel) -> &'static [(f32, u32)] {
+    match level {
+        // One soft chime
+        AlertLevel::Info => &[(880.0, 180)],
+        // Two descending tones
+        AlertLevel::Warning => &[(988.0, 160), (0.0, 60), (740.0, 220)],
+        // Three urgent high beeps
+        AlertLevel::Critical => &[(1319.0, 120), (0.0, 60), (1319.0, 120), (0.0, 60), (1319.0, 220)],
+    }
+}
+
+/// Bundled alert sound for `level` as a 16-bit mono WAV file, scaled by `volume`
+pub fn bundled_sound(level: AlertLevel, volume: f32) -> Vec<u8> {
+    let amplitude = volume.clamp(0.0, 1.0) * 0.6 * i16::MAX as f32;
+    let mut samples: Vec<i16> = Vec::new();
+
+    for &(frequency, duration_ms) in tone_pattern(level) {
+        let count = (SAMPLE_RATE * duration_ms / 1000) as usize;
+        for i in 0..count {
+            if frequency == 0.0 {
+                samples.push(0);
+                continue;
+            }
+            // Short linear fade in/out avoids clicks
+            let fade = (i.min(count - i) as f32 / (SAMPLE_RATE as f32 * 0.01)).min(1.0);
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let value = (2.0 * std::f32::consts::PI * frequency * t).sin() * amplitude * fade;
+            samples.push(value as i16);
+        }
+    }
+
+    encode_wav(&samples)
+}
+
+fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
+
+/// Outcome of trying to play an alert sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+    Started,
+    /// No usable audio player; the caller may ask the notification server to play a sound
+    Unavailable,
+}
+
+#[derive(Clone)]
+pub struct SoundPlayer {
+    cache_dir: PathBuf,
+    /// Set when the last background playback found no working player
+    unavailable: Arc<AtomicBool>,
+}
+
+impl Default for SoundPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoundPlayer {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("orb-credit-monitor")
+                .join("sounds"),
+            unavailable: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether the last background playback found no working player
+    pub fn is_unavailable(&self) -> bool {
+        self.unavailable.load(Ordering::Relaxed)
+    }
+
+    /// Forget a previous failure, e.g. after the sound settings changed
+    pub fn reset_availability(&self) {
+        self.unavailable.store(false, Ordering::Relaxed);
+    }
+
+    /// Play the sound for `level` on a background task. Checking a player takes up to
+    /// `PLAYER_CHECK_TIMEOUT`, which callers holding a lock shouldn't wait for.
+    pub fn play_in_background(&self, level: AlertLevel, settings: SoundSettings) {
+        let player = self.clone();
+        tokio::spawn(async move {
+            let playback = player.play(level, &settings).await.unwrap_or_else(|e| {
+                tracing::warn!("⚠️ Failed to play alert sound: {}", e);
+                Playback::Unavailable
+            });
+            if playback == Playback::Unavailable {
+                tracing::warn!("⚠️ No audio player available for alert sounds");
+            }
+            player.unavailable.store(playback == Playback::Unavailable, Ordering::Relaxed);
+        });
+    }
+
+    /// Play the sound for `level`, trying each player until one doesn't fail right away
+    pub async fn play(&self, level: AlertLevel, settings: &SoundSettings) -> AppResult<Playback> {
+        if settings.volume <= 0.0 {
+            return Ok(Playback::Started);
+        }
+
+        let (path, player_volume) = match settings.custom_sound(level) {
+            Some(path) => (PathBuf::from(path), settings.volume),
+            // Bundled sounds already carry the volume in their samples
+            None => (self.write_bundled(level, settings.volume).await?, 1.0),
+        };
+
+        for command in player_commands(&path, player_volume) {
+            match start_player(command, PLAYER_CHECK_TIMEOUT).await? {
+                PlayerStart::Playing => return Ok(Playback::Started),
+                PlayerStart::Failed | PlayerStart::Missing => continue,
+            }
+        }
+
+        Ok(Playback::Unavailable)
+    }
+
+    async fn write_bundled(&self, level: AlertLevel, volume: f32) -> AppResult<PathBuf> {
+        tokio::fs::create_dir_all(&self.cache_dir).await?;
+        let path = self.cache_dir.join(format!("{}-{}.wav", sound_theme_name(level), (volume * 100.0).round() as u32));
+        if !path.exists() {
+            tokio::fs::write(&path, bundled_sound(level, volume)).await?;
+        }
+        Ok(path)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PlayerStart {
+    /// Finished successfully, or still running after the check timeout
+    Playing,
+    Failed,
+    /// Not installed
+    Missing,
+}
+
+/// Spawn `command` and wait up to `timeout` for it to exit; a player still running by then is
+/// left to finish in the background
+async fn start_player(mut command: Command, timeout: Duration) -> AppResult<PlayerStart> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(PlayerStart::Missing),
+        Err(e) => return Err(AppError::Io(e)),
+    };
+
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(PlayerStart::Playing),
+        Ok(Ok(status)) => {
+            tracing::warn!("⚠️ Audio player {} failed: {}", program, status);
+            Ok(PlayerStart::Failed)
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("⚠️ Audio player {} failed: {}", program, e);
+            Ok(PlayerStart::Failed)
+        }
+        Err(_) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+            Ok(PlayerStart::Playing)
+        }
+    }
+}
+
+/// Candidate players for this platform, in order of preference
+fn player_commands(path: &Path, volume: f32) -> Vec<Command> {
+    let mut commands = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        let mut afplay = Command::new("afplay");
+        afplay.arg("-v").arg(format!("{:.2}", volume)).arg(path);
+        commands.push(afplay);
+    } else if cfg!(target_os = "windows") {
+        // SoundPlayer has no volume control; custom files play at their own level
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.display().to_string().replace('\'', "''")
+        );
+        let mut powershell = Command::new("powershell");
+        powershell.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        commands.push(powershell);
+    } else {
+        let mut paplay = Command::new("paplay");
+        paplay.arg(format!("--volume={}", (volume * 65536.0) as u32)).arg(path);
+        commands.push(paplay);
+
+        let mut pw_play = Command::new("pw-play");
+        pw_play.arg(format!("--volume={:.2}", volume)).arg(path);
+        commands.push(pw_play);
+
+        let mut aplay = Command::new("aplay");
+        aplay.arg("-q").arg(path);
+        commands.push(aplay);
+    }
+
+    for command in &mut commands {
+        command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(wav: &[u8]) -> Vec<i16> {
+        wav[44..].chunks(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect()
+    }
+
+    #[test]
+    fn test_bundled_sound_is_valid_wav() {
+        let wav = bundled_sound(AlertLevel::Warning, 1.0);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+
+        let data_len = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) as usize;
+        assert_eq!(data_len, wav.len() - 44);
+        assert_eq!(u32::from_le_bytes([wav[4], wav[5], wav[6], wav[7]]) as usize, wav.len() - 8);
+    }
+
+    #[test]
+    fn test_missing_custom_sound_falls_back_to_bundled() {
+        let settings = SoundSettings {
+            warning_sound: Some("/no/such/dir/alert.wav".to_string()),
+            ..SoundSettings::default()
+        };
+
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.custom_sound(AlertLevel::Warning), None);
+    }
+
+    #[test]
+    fn test_levels_sound_different() {
+        let info = bundled_sound(AlertLevel::Info, 1.0);
+        let warning = bundled_sound(AlertLevel::Warning, 1.0);
+        let critical = bundled_sound(AlertLevel::Critical, 1.0);
+
+        assert_ne!(info, warning);
+        assert_ne!(warning, critical);
+        assert!(critical.len() > info.len());
+    }
+
+    #[test]
+    fn test_volume_scales_samples() {
+        let peak = |volume: f32| samples(&bundled_sound(AlertLevel::Info, volume))
+            .into_iter()
+            .map(|s| (s as i32).abs())
+            .max()
+            .unwrap();
+
+        let loud = peak(1.0);
+        let quiet = peak(0.25);
+        assert!(loud > 0);
+        assert!((quiet as f32 / loud as f32 - 0.25).abs() < 0.01);
+        assert_eq!(peak(0.0), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_player_exit_status_is_checked() {
+        let timeout = Duration::from_millis(500);
+        let shell = |script: &str| {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script);
+            command
+        };
+
+        assert_eq!(start_player(shell("exit 0"), timeout).await.unwrap(), PlayerStart::Playing);
+        assert_eq!(start_player(shell("exit 1"), timeout).await.unwrap(), PlayerStart::Failed);
+        assert_eq!(start_player(shell("sleep 5"), timeout).await.unwrap(), PlayerStart::Playing);
+        assert_eq!(
+            start_player(Command::new("no-such-audio-player"), timeout).await.unwrap(),
+            PlayerStart::Missing
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::database::{DailyConsumptionRecord, UsageRecord};
+
+/// Which history the statistics are computed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticsSource {
+    /// Balance deltas sampled by this app
+    Local,
+    /// Daily consumption reported by the Augment API
+    Server,
+    /// Server figures where available, local deltas for the remaining days
+    #[default]
+    Combined,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyPoint {
+    pub date: NaiveDate,
+    pub credits: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovingAveragePoint {
+    pub date: NaiveDate,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusiestPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub credits: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageStatistics {
+    pub source: StatisticsSource,
+    pub days_analyzed: usize,
+    pub daily_series: Vec<DailyPoint>,
+    pub moving_average_7d: Vec<MovingAveragePoint>,
+    pub moving_average_30d: Vec<MovingAveragePoint>,
+    pub latest_moving_average_7d: Option<f64>,
+    pub latest_moving_average_30d: Option<f64>,
+    pub mean_daily_usage: f64,
+    pub median_daily_usage: Option<f64>,
+    pub p90_daily_usage: Option<f64>,
+    pub p99_daily_usage: Option<f64>,
+    pub cycle_start: NaiveDate,
+    pub busiest_day: Option<BusiestPeriod>,
+    pub busiest_week: Option<BusiestPeriod>,
+}
+
+/// Billing cycles are monthly, so the current cycle started one month before `billing_period_end`
+pub fn cycle_start_from_billing_period_end(billing_period_end: &str) -> Option<NaiveDate> {
+    let end = DateTime::parse_from_rfc3339(billing_period_end)
+        .map(|dt| dt.date_naive())
+        .or_else(|_| NaiveDate::parse_from_str(billing_period_end.split('T').next().unwrap_or(""), "%Y-%m-%d"))
+        .ok()?;
+
+    end.checked_sub_months(Months::new(1))
+}
+
+/// Share (0.0 - 1.0) of the current monthly billing cycle that has elapsed at `now`
+pub fn cycle_elapsed_fraction(billing_period_end: &str, now: DateTime<Utc>) -> Option<f64> {
+    let end = DateTime::parse_from_rfc3339(billing_period_end).ok()?.with_timezone(&Utc);
+    let start = end.checked_sub_months(Months::new(1))?;
+
+    let total = (end - start).num_seconds() as f64;
+    let elapsed = (now - start).num_seconds() as f64;
+    (total > 0.0).then(|| (elapsed / total).clamp(0.0, 1.0))
+}
+
+/// Sum local usage records per UTC day.
+///
+/// Records are stamped at the end of the interval they cover, so one stamped exactly at midnight
+/// (every backfilled day) counts towards the day that just ended.
+pub fn local_daily_totals(usage_history: &[UsageRecord]) -> BTreeMap<NaiveDate, i64> {
+    let mut totals = BTreeMap::new();
+    for record in usage_history {
+        let day = (record.timestamp - Duration::seconds(1)).date_naive();
+        *totals.entry(day).or_insert(0) += record.usage_amount as i64;
+    }
+    totals
+}
+
+pub fn server_daily_totals(consumption: &[DailyConsumptionRecord]) -> BTreeMap<NaiveDate, i64> {
+    consumption.iter()
+        .filter_map(|record| {
+            NaiveDate::parse_from_str(&record.date, "%Y-%m-%d")
+                .ok()
+                .map(|date| (date, record.credits))
+        })
+        .collect()
+}
+
+/// Merge both histories according to `source`
+pub fn select_daily_totals(
+    source: StatisticsSource,
+    local: BTreeMap<NaiveDate, i64>,
+    server: BTreeMap<NaiveDate, i64>,
+) -> BTreeMap<NaiveDate, i64> {
+    match source {
+        StatisticsSource::Local => local,
+        StatisticsSource::Server => server,
+        StatisticsSource::Combined => {
+            let mut combined = local;
+            combined.extend(server);
+            combined
+        }
+    }
+}
+
+/// Turn sparse per-day totals into a contiguous series from the first day with data to `today`.
+/// Days without data inside that span count as zero usage.
+pub fn build_daily_series(totals: &BTreeMap<NaiveDate, i64>, today: NaiveDate) -> Vec<DailyPoint> {
+    let first = match totals.keys().next() {
+        Some(first) => *first,
+        None => return Vec::new(),
+    };
+
+    let mut series = Vec::new();
+    let mut date = first;
+    while date <= today {
+        series.push(DailyPoint {
+            date,
+            credits: totals.get(&date).copied().unwrap_or(0),
+        });
+        date += Duration::days(1);
+    }
+    series
+}
+
+/// Trailing moving average; the first point is emitted once a full window is available
+pub fn moving_average(series: &[DailyPoint], window: usize) -> Vec<MovingAveragePoint> {
+    if window == 0 || series.len() < window {
+        return Vec::new();
+    }
+
+    series.windows(window)
+        .map(|days| MovingAveragePoint {
+            date: days[window - 1].date,
+            value: days.iter().map(|d| d.credits as f64).sum::<f64>() / window as f64,
+        })
+        .collect()
+}
+
+/// Percentile with linear interpolation between closest ranks (`p` in 0..=100)
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+}
+
+fn busiest_day(series: &[DailyPoint], cycle_start: NaiveDate) -> Option<BusiestPeriod> {
+    series.iter()
+        .filter(|d| d.date >= cycle_start && d.credits > 0)
+        .max_by_key(|d| d.credits)
+        .map(|d| BusiestPeriod { start: d.date, end: d.date, credits: d.credits })
+}
+
+/// Busiest ISO week (Monday to Sunday), clipped to the current cycle
+fn busiest_week(series: &[DailyPoint], cycle_start: NaiveDate) -> Option<BusiestPeriod> {
+    let mut weeks: BTreeMap<(i32, u32), BusiestPeriod> = BTreeMap::new();
+
+    for day in series.iter().filter(|d| d.date >= cycle_start) {
+        let week = day.date.iso_week();
+        let entry = weeks.entry((week.year(), week.week())).or_insert(BusiestPeriod {
+            start: day.date,
+            end: day.date,
+            credits: 0,
+        });
+        entry.end = day.date;
+        entry.credits += day.credits;
+    }
+
+    weeks.into_values()
+        .filter(|w| w.credits > 0)
+        .max_by_key(|w| w.credits)
+}
+
+pub fn calculate_statistics(
+    source: StatisticsSource,
+    totals: &BTreeMap<NaiveDate, i64>,
+    today: NaiveDate,
+    cycle_start: NaiveDate,
+) -> UsageStatistics {
+    let daily_series = build_daily_series(totals, today);
+    let values: Vec<f64> = daily_series.iter().map(|d| d.credits as f64).collect();
+
+    let moving_average_7d = moving_average(&daily_series, 7);
+    let moving_average_30d = moving_average(&daily_series, 30);
+
+    let mean_daily_usage = if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    };
+
+    UsageStatistics {
+        source,
+        days_analyzed: daily_series.len(),
+        latest_moving_average_7d: moving_average_7d.last().map(|p| p.value),
+        latest_moving_average_30d: moving_average_30d.last().map(|p| p.value),
+        mean_daily_usage,
+        median_daily_usage: percentile(&values, 50.0),
+        p90_daily_usage: percentile(&values, 90.0),
+        p99_daily_usage: percentile(&values, 99.0),
+        cycle_start,
+        busiest_day: busiest_day(&daily_series, cycle_start),
+        busiest_week: busiest_week(&daily_series, cycle_start),
+        daily_series,
+        moving_average_7d,
+        moving_average_30d,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, day).unwrap()
+    }
+
+    #[test]
+    fn test_percentile_interpolates() {
+        let values = vec![10.0, 20.0, 30.0, 40.0, 50.0];
+        assert_eq!(percentile(&values, 50.0), Some(30.0));
+        assert_eq!(percentile(&values, 90.0), Some(46.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn test_series_zero_fills_and_moving_average() {
+        let totals: BTreeMap<NaiveDate, i64> = [(date(1), 70), (date(4), 140)].into_iter().collect();
+        let series = build_daily_series(&totals, date(7));
+
+        assert_eq!(series.len(), 7);
+        assert_eq!(series[1].credits, 0);
+
+        let ma = moving_average(&series, 7);
+        assert_eq!(ma.len(), 1);
+        assert_eq!(ma[0].date, date(7));
+        assert!((ma[0].value - 30.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_backfilled_day_counts_towards_the_day_it_covers() {
+        let record = |timestamp: DateTime<Utc>, usage_amount: u32, synthetic: bool| UsageRecord {
+            id: uuid::Uuid::new_v4(),
+            start_balance: 1000,
+            end_balance: 1000 - usage_amount,
+            usage_amount,
+            duration_minutes: 60,
+            timestamp,
+            synthetic,
+        };
+        let midnight = date(3).and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+        // Backfill stamps the usage of the 2nd at midnight starting the 3rd
+        let history = vec![
+            record(midnight, 40, true),
+            record(midnight + Duration::hours(10), 5, false),
+        ];
+        let totals = local_daily_totals(&history);
+
+        assert_eq!(totals.get(&date(2)), Some(&40));
+        assert_eq!(totals.get(&date(3)), Some(&5));
+    }
+
+    #[test]
+    fn test_combined_prefers_server_totals() {
+        let local: BTreeMap<NaiveDate, i64> = [(date(1), 5), (date(2), 8)].into_iter().collect();
+        let server: BTreeMap<NaiveDate, i64> = [(date(1), 6)].into_iter().collect();
+
+        let combined = select_daily_totals(StatisticsSource::Combined, local, server);
+        assert_eq!(combined.get(&date(1)), Some(&6));
+        assert_eq!(combined.get(&date(2)), Some(&8));
+    }
+
+    #[test]
+    fn test_busiest_periods_respect_cycle_start() {
+        // 2025-11-03 is a Monday
+        let totals: BTreeMap<NaiveDate, i64> = [
+            (date(1), 500),
+            (date(3), 40),
+            (date(4), 60),
+            (date(10), 80),
+        ].into_iter().collect();
+
+        let stats = calculate_statistics(StatisticsSource::Local, &totals, date(12), date(3));
+
+        let day = stats.busiest_day.unwrap();
+        assert_eq!((day.start, day.credits), (date(10), 80));
+
+        let week = stats.busiest_week.unwrap();
+        assert_eq!((week.start, week.end, week.credits), (date(3), date(9), 100));
+    }
+}
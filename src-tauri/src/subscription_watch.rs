@@ -0,0 +1,368 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use crate::analytics::AlertLevel;
+use crate::augment_client::SubscriptionResponse;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionAlertSettings {
+    pub enabled: bool,
+    /// Warn this many days before a trial or the subscription ends
+    pub trial_warning_days: u32,
+    /// Remind this many days before the billing cycle renews
+    pub renewal_reminder_days: u32,
+}
+
+impl Default for SubscriptionAlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trial_warning_days: 3,
+            renewal_reminder_days: 1,
+        }
+    }
+}
+
+/// The subscription fields worth watching, as seen at `taken_at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionSnapshot {
+    pub plan_id: String,
+    pub plan_name: String,
+    pub billing_period_end: Option<DateTime<Utc>>,
+    pub trial_period_end: Option<DateTime<Utc>>,
+    pub subscription_end_date: Option<DateTime<Utc>>,
+    pub plan_is_expired: bool,
+    pub cancelled_due_to_payment_failure: bool,
+    pub scheduled_target_plan_id: Option<String>,
+    pub next_billing_cycle_plan_name: String,
+    /// `None` when the response was missing the seat count or sent it with the wrong type
+    pub seats_this_cycle: Option<i32>,
+    pub seats_next_cycle: Option<i32>,
+    pub taken_at: DateTime<Utc>,
+}
+
+impl SubscriptionSnapshot {
+    pub fn from_response(subscription: &SubscriptionResponse, taken_at: DateTime<Utc>) -> Self {
+        Self {
+            plan_id: subscription.plan_id.clone(),
+            plan_name: subscription.plan_name.clone(),
+            billing_period_end: parse_date(&subscription.billing_period_end),
+            trial_period_end: subscription.trial_period_end.as_deref().and_then(parse_date),
+            subscription_end_date: subscription.subscription_end_date.as_deref().and_then(parse_date),
+            plan_is_expired: subscription.plan_is_expired,
+            cancelled_due_to_payment_failure: subscription.cancelled_due_to_payment_failure,
+            scheduled_target_plan_id: subscription.scheduled_target_plan_id.clone().filter(|id| !id.is_empty()),
+            next_billing_cycle_plan_name: subscription.next_billing_cycle_plan_name.clone(),
+            seats_this_cycle: subscription.number_of_seats_this_billing_cycle,
+            seats_next_cycle: subscription.number_of_seats_next_billing_cycle,
+            taken_at,
+        }
+    }
+}
+
+/// Accepts RFC 3339 timestamps and plain dates
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc())
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionEventKind {
+    TrialEnding,
+    RenewalUpcoming,
+    SubscriptionEnding,
+    PaymentFailed,
+    PlanExpired,
+    PlanChangeScheduled,
+    PlanChanged,
+    SeatsChanged,
+}
+
+impl SubscriptionEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionEventKind::TrialEnding => "trial_ending",
+            SubscriptionEventKind::RenewalUpcoming => "renewal_upcoming",
+            SubscriptionEventKind::SubscriptionEnding => "subscription_ending",
+            SubscriptionEventKind::PaymentFailed => "payment_failed",
+            SubscriptionEventKind::PlanExpired => "plan_expired",
+            SubscriptionEventKind::PlanChangeScheduled => "plan_change_scheduled",
+            SubscriptionEventKind::PlanChanged => "plan_changed",
+            SubscriptionEventKind::SeatsChanged => "seats_changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionEvent {
+    pub kind: SubscriptionEventKind,
+    pub level: AlertLevel,
+    pub title: String,
+    pub message: String,
+}
+
+impl SubscriptionEvent {
+    fn new(kind: SubscriptionEventKind, level: AlertLevel, title: &str, message: String) -> Self {
+        Self { kind, level, title: title.to_string(), message }
+    }
+}
+
+/// Whether `end` is still ahead of `at` but no more than `days` away
+fn within(end: Option<DateTime<Utc>>, at: DateTime<Utc>, days: u32) -> bool {
+    end.is_some_and(|end| end > at && end - at <= Duration::days(days as i64))
+}
+
+/// Reminder for a deadline: raised once, when the deadline first comes within `days`
+fn entered_window(
+    end: Option<DateTime<Utc>>,
+    previous: Option<(Option<DateTime<Utc>>, DateTime<Utc>)>,
+    now: DateTime<Utc>,
+    days: u32,
+) -> bool {
+    if !within(end, now, days) {
+        return false;
+    }
+    match previous {
+        Some((previous_end, taken_at)) => previous_end != end || !within(previous_end, taken_at, days),
+        None => true,
+    }
+}
+
+fn describe_remaining(end: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let hours = (end - now).num_hours();
+    if hours < 24 {
+        format!("in {} hours", hours.max(1))
+    } else {
+        format!("in {} days", (hours + 12) / 24)
+    }
+}
+
+/// Events raised by going from `previous` to `current`. Without a previous snapshot only
+/// deadlines and problems are reported, since there is nothing to compare changes against.
+pub fn diff(
+    previous: Option<&SubscriptionSnapshot>,
+    current: &SubscriptionSnapshot,
+    settings: &SubscriptionAlertSettings,
+    now: DateTime<Utc>,
+) -> Vec<SubscriptionEvent> {
+    use SubscriptionEventKind::*;
+
+    let mut events = Vec::new();
+
+    if current.cancelled_due_to_payment_failure && !previous.is_some_and(|p| p.cancelled_due_to_payment_failure) {
+        events.push(SubscriptionEvent::new(
+            PaymentFailed,
+            AlertLevel::Critical,
+            "Payment Failed",
+            format!("Your {} subscription was cancelled because a payment failed. Update your payment method to keep your credits.", current.plan_name),
+        ));
+    }
+
+    if current.plan_is_expired && !previous.is_some_and(|p| p.plan_is_expired) {
+        events.push(SubscriptionEvent::new(
+            PlanExpired,
+            AlertLevel::Critical,
+            "Plan Expired",
+            format!("Your {} plan has expired.", current.plan_name),
+        ));
+    }
+
+    if entered_window(
+        current.trial_period_end,
+        previous.map(|p| (p.trial_period_end, p.taken_at)),
+        now,
+        settings.trial_warning_days,
+    ) {
+        if let Some(end) = current.trial_period_end {
+            events.push(SubscriptionEvent::new(
+                TrialEnding,
+                AlertLevel::Warning,
+                "Trial Ending Soon",
+                format!("Your {} trial ends {}.", current.plan_name, describe_remaining(end, now)),
+            ));
+        }
+    }
+
+    if entered_window(
+        current.subscription_end_date,
+        previous.map(|p| (p.subscription_end_date, p.taken_at)),
+        now,
+        settings.trial_warning_days,
+    ) {
+        if let Some(end) = current.subscription_end_date {
+            events.push(SubscriptionEvent::new(
+                SubscriptionEnding,
+                AlertLevel::Warning,
+                "Subscription Ending",
+                format!("Your {} subscription ends {}.", current.plan_name, describe_remaining(end, now)),
+            ));
+        }
+    }
+
+    // A trial or a subscription that is ending has its own reminder
+    let renews = current.trial_period_end.is_none() && current.subscription_end_date.is_none() && !current.plan_is_expired;
+    if renews && entered_window(
+        current.billing_period_end,
+        previous.map(|p| (p.billing_period_end, p.taken_at)),
+        now,
+        settings.renewal_reminder_days,
+    ) {
+        if let Some(end) = current.billing_period_end {
+            events.push(SubscriptionEvent::new(
+                RenewalUpcoming,
+                AlertLevel::Info,
+                "Billing Cycle Renewing",
+                format!("Your {} plan renews {}.", current.next_billing_cycle_plan_name, describe_remaining(end, now)),
+            ));
+        }
+    }
+
+    let Some(previous) = previous else {
+        return events;
+    };
+
+    if current.scheduled_target_plan_id.is_some() && current.scheduled_target_plan_id != previous.scheduled_target_plan_id {
+        events.push(SubscriptionEvent::new(
+            PlanChangeScheduled,
+            AlertLevel::Info,
+            "Plan Change Scheduled",
+            format!("Your plan will change from {} to {} at the next billing cycle.", current.plan_name, current.next_billing_cycle_plan_name),
+        ));
+    }
+
+    if current.plan_id != previous.plan_id {
+        events.push(SubscriptionEvent::new(
+            PlanChanged,
+            AlertLevel::Info,
+            "Plan Changed",
+            format!("Your plan changed from {} to {}.", previous.plan_name, current.plan_name),
+        ));
+    }
+
+    // An unknown seat count is not a change
+    if let (Some(before), Some(after)) = (previous.seats_next_cycle, current.seats_next_cycle) {
+        if before != after {
+            let currently = current.seats_this_cycle
+                .map(|seats| format!(" (currently {})", seats))
+                .unwrap_or_default();
+            events.push(SubscriptionEvent::new(
+                SeatsChanged,
+                AlertLevel::Info,
+                "Seat Count Changed",
+                format!("Seats next billing cycle: {} → {}{}.", before, after, currently),
+            ));
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn snapshot(taken_at: DateTime<Utc>) -> SubscriptionSnapshot {
+        SubscriptionSnapshot {
+            plan_id: "dev".to_string(),
+            plan_name: "Developer".to_string(),
+            billing_period_end: Some(at(30, 0)),
+            trial_period_end: None,
+            subscription_end_date: None,
+            plan_is_expired: false,
+            cancelled_due_to_payment_failure: false,
+            scheduled_target_plan_id: None,
+            next_billing_cycle_plan_name: "Developer".to_string(),
+            seats_this_cycle: Some(1),
+            seats_next_cycle: Some(1),
+            taken_at,
+        }
+    }
+
+    fn kinds(events: &[SubscriptionEvent]) -> Vec<SubscriptionEventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        assert_eq!(parse_date("2025-06-30T00:00:00Z"), Some(at(30, 0)));
+        assert_eq!(parse_date("2025-06-30"), Some(at(30, 0)));
+        assert_eq!(parse_date("soon"), None);
+    }
+
+    #[test]
+    fn test_trial_reminder_fires_once() {
+        let settings = SubscriptionAlertSettings::default();
+        let mut first = snapshot(at(20, 0));
+        first.trial_period_end = Some(at(25, 0));
+
+        // Five days out: nothing yet
+        assert!(diff(None, &first, &settings, at(20, 0)).is_empty());
+
+        let mut second = first.clone();
+        second.taken_at = at(22, 12);
+        let events = diff(Some(&first), &second, &settings, at(22, 12));
+        assert_eq!(kinds(&events), vec![SubscriptionEventKind::TrialEnding]);
+        assert!(events[0].message.ends_with("in 3 days."));
+
+        let mut third = second.clone();
+        third.taken_at = at(23, 0);
+        assert!(diff(Some(&second), &third, &settings, at(23, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_renewal_and_changes() {
+        let settings = SubscriptionAlertSettings::default();
+        let previous = snapshot(at(28, 0));
+
+        let mut current = snapshot(at(29, 6));
+        current.scheduled_target_plan_id = Some("pro".to_string());
+        current.next_billing_cycle_plan_name = "Pro".to_string();
+        current.seats_next_cycle = Some(3);
+
+        let events = diff(Some(&previous), &current, &settings, at(29, 6));
+        assert_eq!(kinds(&events), vec![
+            SubscriptionEventKind::RenewalUpcoming,
+            SubscriptionEventKind::PlanChangeScheduled,
+            SubscriptionEventKind::SeatsChanged,
+        ]);
+        assert_eq!(events[0].message, "Your Pro plan renews in 18 hours.");
+        assert_eq!(events[2].message, "Seats next billing cycle: 1 → 3 (currently 1).");
+    }
+
+    #[test]
+    fn test_unknown_seat_count_is_not_a_change() {
+        let settings = SubscriptionAlertSettings::default();
+        let previous = snapshot(at(10, 0));
+
+        let mut current = snapshot(at(11, 0));
+        current.seats_next_cycle = None;
+        assert!(diff(Some(&previous), &current, &settings, at(11, 0)).is_empty());
+        assert!(diff(Some(&current), &previous, &settings, at(12, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_payment_failure_reported_once() {
+        let settings = SubscriptionAlertSettings::default();
+        let mut failed = snapshot(at(10, 0));
+        failed.cancelled_due_to_payment_failure = true;
+
+        let events = diff(None, &failed, &settings, at(10, 0));
+        assert_eq!(kinds(&events), vec![SubscriptionEventKind::PaymentFailed]);
+        assert_eq!(events[0].level, AlertLevel::Critical);
+
+        assert!(diff(Some(&failed), &failed, &settings, at(11, 0)).is_empty());
+    }
+}
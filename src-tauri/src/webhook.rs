@@ -0,0 +1,467 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::alert_state::{self, AlertTransition, TransitionKind};
+use crate::analytics::AlertLevel;
+use crate::error::{AppError, AppResult};
+use crate::network::NetworkSettings;
+
+/// Header carrying the hex HMAC-SHA256 of `"{timestamp}.{body}"`
+pub const SIGNATURE_HEADER: &str = "X-Orb-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Orb-Timestamp";
+
+/// Body sent when a webhook has no template of its own
+pub const DEFAULT_TEMPLATE: &str = r#"{
+  "rule_id": "{{rule_id}}",
+  "rule_name": "{{rule_name}}",
+  "event": "{{event}}",
+  "severity": "{{severity}}",
+  "message": "{{message}}",
+  "balance": "{{balance}}",
+  "value": "{{value}}",
+  "threshold": "{{threshold}}",
+  "timestamp": "{{timestamp}}"
+}"#;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Shared secret used to sign the body; unsigned when absent
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// JSON template with `{{field}}` placeholders; `DEFAULT_TEMPLATE` when absent
+    #[serde(default)]
+    pub body_template: Option<String>,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+impl WebhookConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("webhook id cannot be empty".to_string());
+        }
+        let url = url::Url::parse(&self.url).map_err(|e| format!("invalid URL: {}", e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err("URL must use http or https".to_string());
+        }
+        if self.timeout_seconds == 0 || self.timeout_seconds > 120 {
+            return Err("timeout must be between 1 and 120 seconds".to_string());
+        }
+        if self.max_retries > 10 {
+            return Err("at most 10 retries are allowed".to_string());
+        }
+        if let Some(template) = &self.body_template {
+            serde_json::from_str::<Value>(template)
+                .map_err(|e| format!("body template is not valid JSON: {}", e))?;
+        }
+        for name in self.headers.keys() {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("invalid header name '{}'", name))?;
+        }
+        Ok(())
+    }
+}
+
+/// The fields available to body templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookAlert {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub event: TransitionKind,
+    pub severity: AlertLevel,
+    pub message: String,
+    pub balance: Option<u32>,
+    pub value: Option<f64>,
+    pub threshold: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl WebhookAlert {
+    pub fn from_transition(transition: &AlertTransition, balance: Option<u32>) -> Self {
+        Self {
+            rule_id: transition.rule_id.clone(),
+            rule_name: transition.rule_name.clone(),
+            event: transition.kind,
+            severity: transition.severity,
+            message: transition.message.clone(),
+            balance,
+            value: transition.value,
+            threshold: transition.threshold,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn field(&self, name: &str) -> Option<Value> {
+        let value = match name {
+            "rule_id" => Value::from(self.rule_id.as_str()),
+            "rule_name" => Value::from(self.rule_name.as_str()),
+            "event" => serde_json::to_value(self.event).unwrap_or(Value::Null),
+            "severity" => Value::from(alert_state::level_as_str(self.severity)),
+            "message" => Value::from(self.message.as_str()),
+            "balance" => self.balance.map(Value::from).unwrap_or(Value::Null),
+            "value" => self.value.map(Value::from).unwrap_or(Value::Null),
+            "threshold" => Value::from(self.threshold),
+            "timestamp" => Value::from(self.timestamp.to_rfc3339()),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Render `template` for `alert`. A string that is exactly one placeholder is replaced by the
+/// field's JSON value (so numbers stay numbers); placeholders inside longer strings are
+/// interpolated as text. Unknown placeholders are left untouched.
+pub fn render_template(template: &str, alert: &WebhookAlert) -> AppResult<String> {
+    let mut body: Value = serde_json::from_str(template)?;
+    render_value(&mut body, alert);
+    Ok(serde_json::to_string(&body)?)
+}
+
+fn render_value(value: &mut Value, alert: &WebhookAlert) {
+    match value {
+        Value::String(text) => {
+            if let Some(name) = text.strip_prefix("{{").and_then(|t| t.strip_suffix("}}")) {
+                if let Some(field) = alert.field(name.trim()) {
+                    *value = field;
+                    return;
+                }
+            }
+            *text = interpolate(text, alert);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| render_value(item, alert)),
+        Value::Object(map) => map.values_mut().for_each(|item| render_value(item, alert)),
+        _ => {}
+    }
+}
+
+fn interpolate(text: &str, alert: &WebhookAlert) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        let name = rest[start + 2..start + end].trim();
+
+        result.push_str(&rest[..start]);
+        match alert.field(name) {
+            Some(Value::String(s)) => result.push_str(&s),
+            Some(Value::Null) => {}
+            Some(other) => result.push_str(&other.to_string()),
+            None => result.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Hex HMAC-SHA256 over `"{timestamp}.{body}"`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub webhook_id: String,
+    pub status: Option<u16>,
+    pub attempts: u32,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct WebhookSender {
+    client: reqwest::Client,
+    retry_base_delay: Duration,
+}
+
+impl Default for WebhookSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookSender {
+    pub fn new() -> Self {
+        Self::with_network(&NetworkSettings::default())
+    }
+
+    /// Sender going through the configured proxy and trusting the extra CA certificates
+    pub fn with_network(network: &NetworkSettings) -> Self {
+        let client = network.client_builder()
+            .and_then(|builder| Ok(builder.user_agent("orb-credit-monitor").build()?))
+            .unwrap_or_else(|e| {
+                tracing::error!("❌ Failed to apply network settings to webhooks: {}", e);
+                reqwest::Client::default()
+            });
+
+        Self {
+            client,
+            retry_base_delay: Duration::from_millis(500),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_base_delay = delay;
+        self
+    }
+
+    /// POST the rendered alert, retrying network errors, 429 and 5xx with exponential backoff
+    pub async fn send(&self, webhook: &WebhookConfig, alert: &WebhookAlert) -> WebhookDelivery {
+        let template = webhook.body_template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+        match render_template(template, alert) {
+            Ok(body) => self.deliver(webhook, body).await,
+            Err(e) => WebhookDelivery {
+                webhook_id: webhook.id.clone(),
+                status: None,
+                attempts: 0,
+                success: false,
+                error: Some(format!("Failed to render template: {}", e)),
+            },
+        }
+    }
+
+    /// POST an already rendered JSON body with the webhook's headers, signing and retry policy
+    pub async fn deliver(&self, webhook: &WebhookConfig, body: String) -> WebhookDelivery {
+        let mut delivery = WebhookDelivery {
+            webhook_id: webhook.id.clone(),
+            status: None,
+            attempts: 0,
+            success: false,
+            error: None,
+        };
+
+        loop {
+            delivery.attempts += 1;
+
+            let retryable = match self.post(webhook, &body).await {
+                Ok(status) => {
+                    delivery.status = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.success = true;
+                        delivery.error = None;
+                        return delivery;
+                    }
+                    delivery.error = Some(format!("HTTP {}", status));
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => {
+                    delivery.error = Some(e.to_string());
+                    true
+                }
+            };
+
+            if !retryable || delivery.attempts > webhook.max_retries {
+                tracing::warn!(
+                    "⚠️ Webhook '{}' failed after {} attempt(s): {}",
+                    webhook.name,
+                    delivery.attempts,
+                    delivery.error.as_deref().unwrap_or("unknown error")
+                );
+                return delivery;
+            }
+
+            let delay = self.retry_base_delay * 2u32.saturating_pow(delivery.attempts - 1);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn post(&self, webhook: &WebhookConfig, body: &str) -> AppResult<reqwest::StatusCode> {
+        let mut request = self.client
+            .post(&webhook.url)
+            .timeout(Duration::from_secs(webhook.timeout_seconds))
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+        for (name, value) in &webhook.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        if let Some(secret) = webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+            let timestamp = Utc::now().timestamp();
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, timestamp, body)));
+        }
+
+        let response = request.body(body.to_string()).send().await
+            .map_err(|e| if e.is_timeout() { AppError::Timeout } else { AppError::Http(e) })?;
+        Ok(response.status())
+    }
+}
+
+pub fn validate_webhooks(webhooks: &[WebhookConfig]) -> AppResult<()> {
+    let mut ids = std::collections::HashSet::new();
+    for webhook in webhooks {
+        let invalid = |reason: String| AppError::Config(
+            config::ConfigError::Message(format!("Webhook '{}': {}", webhook.id, reason))
+        );
+        webhook.validate().map_err(invalid)?;
+        if !ids.insert(webhook.id.as_str()) {
+            return Err(invalid("duplicate webhook id".to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn alert() -> WebhookAlert {
+        WebhookAlert {
+            rule_id: "low_balance".to_string(),
+            rule_name: "Low \"Balance\"".to_string(),
+            event: TransitionKind::Fired,
+            severity: AlertLevel::Warning,
+            message: "Only 420 credits left".to_string(),
+            balance: Some(420),
+            value: Some(420.0),
+            threshold: 500.0,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn webhook(url: String) -> WebhookConfig {
+        WebhookConfig {
+            id: "ops".to_string(),
+            name: "Ops".to_string(),
+            url,
+            enabled: true,
+            headers: HashMap::from([("X-Team".to_string(), "billing".to_string())]),
+            secret: Some("s3cret".to_string()),
+            body_template: Some(r#"{"text": "{{rule_name}}: {{message}}", "balance": "{{balance}}"}"#.to_string()),
+            timeout_seconds: 5,
+            max_retries: 2,
+        }
+    }
+
+    #[test]
+    fn test_render_template_escapes_and_keeps_types() {
+        let body = render_template(
+            r#"{"text": "[{{severity}}] {{rule_name}}", "balance": "{{balance}}", "list": ["{{event}}", "{{unknown}}"]}"#,
+            &alert(),
+        ).unwrap();
+        let parsed: Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(parsed["text"], "[warning] Low \"Balance\"");
+        assert_eq!(parsed["balance"], 420);
+        assert_eq!(parsed["list"][0], "fired");
+        assert_eq!(parsed["list"][1], "{{unknown}}");
+    }
+
+    #[test]
+    fn test_signature_is_stable() {
+        assert_eq!(sign("key", 1, "{}"), sign("key", 1, "{}"));
+        assert_ne!(sign("key", 1, "{}"), sign("other", 1, "{}"));
+        assert_eq!(sign("key", 1, "{}").len(), 64);
+    }
+
+    /// Minimal HTTP server answering with the given status codes in order; returns the raw requests
+    async fn serve(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some(header_end) = text.find("\r\n\r\n") {
+                        let length = text.lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if raw.len() >= header_end + 4 + length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                requests.push(String::from_utf8_lossy(&raw).to_string());
+                let response = format!("HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_and_signs() {
+        let (url, server) = serve(vec![503, 200]).await;
+        let sender = WebhookSender::new().with_retry_delay(Duration::from_millis(10));
+
+        let delivery = sender.send(&webhook(url), &alert()).await;
+        assert!(delivery.success, "{:?}", delivery);
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.status, Some(200));
+
+        let requests = server.await.unwrap();
+        let request = &requests[1];
+        let lower = request.to_ascii_lowercase();
+        assert!(lower.contains("x-team: billing"));
+
+        let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+        let timestamp: i64 = lower.lines()
+            .find_map(|l| l.strip_prefix("x-orb-timestamp:"))
+            .unwrap().trim().parse().unwrap();
+        let expected = format!("sha256={}", sign("s3cret", timestamp, body));
+        assert!(lower.contains(&format!("x-orb-signature: {}", expected)));
+
+        let parsed: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(parsed["balance"], 420);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let (url, server) = serve(vec![400]).await;
+        let sender = WebhookSender::new().with_retry_delay(Duration::from_millis(10));
+
+        let delivery = sender.send(&webhook(url), &alert()).await;
+        assert!(!delivery.success);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.status, Some(400));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+}
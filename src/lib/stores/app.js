@@ -827,6 +827,7 @@ export async function initializeApp(useFreshData = false) {
 
 // Store the unlisten function to prevent garbage collection
 let unlistenBalanceUpdated = null;
+let unlistenSubscriptionUpdated = null;
 
 // Set up event listeners for backend events
 async function setupBackendEventListeners() {
@@ -841,6 +842,12 @@ async function setupBackendEventListeners() {
       connectionStatus.set('connected');
     });
 
+    // Fresh subscription after the dashboard was rendered from a stale cached copy
+    unlistenSubscriptionUpdated = await listen('subscription-updated', (event) => {
+      console.log('📋 Received subscription refresh from backend:', event.payload);
+      subscriptionInfo.set(event.payload);
+    });
+
     console.log('✅ Backend event listeners set up successfully');
   } catch (error) {
     console.error('❌ Failed to set up backend event listeners:', error);
@@ -854,4 +861,8 @@ export function cleanupEventListeners() {
     unlistenBalanceUpdated();
     unlistenBalanceUpdated = null;
   }
+  if (unlistenSubscriptionUpdated) {
+    unlistenSubscriptionUpdated();
+    unlistenSubscriptionUpdated = null;
+  }
 }